@@ -0,0 +1,215 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use {optional, read_attr, read_num, write_attr, Error, Frequency, Result, SYSFS_CPU_ROOT};
+
+/// Operation mode of amd-pstate as read from amd_pstate/status
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AmdPstateStatus {
+    /// The firmware picks the performance level guided by the EPP hint, the scaling driver is amd-pstate-epp
+    Active,
+    /// The generic governors request the performance level, the scaling driver is amd-pstate
+    Passive,
+    /// The generic governors set the lower limit and the firmware picks the performance level
+    /// above it, the scaling driver is amd-pstate
+    Guided,
+    /// amd-pstate is not in use
+    Disable,
+}
+
+impl AmdPstateStatus {
+    /// returns the name as used by the kernel
+    pub fn as_str(&self) -> &str {
+        match *self {
+            AmdPstateStatus::Active => "active",
+            AmdPstateStatus::Passive => "passive",
+            AmdPstateStatus::Guided => "guided",
+            AmdPstateStatus::Disable => "disable",
+        }
+    }
+}
+
+impl fmt::Display for AmdPstateStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AmdPstateStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<AmdPstateStatus> {
+        match s.trim() {
+            "active" => Ok(AmdPstateStatus::Active),
+            "passive" => Ok(AmdPstateStatus::Passive),
+            "guided" => Ok(AmdPstateStatus::Guided),
+            "disable" => Ok(AmdPstateStatus::Disable),
+            _ => Err(Error::InvalidInput {
+                input: s.into(),
+                expected: "active, passive, guided or disable".into(),
+            }),
+        }
+    }
+}
+
+/// find the global amd-pstate settings in /sys/devices/system/cpu/amd_pstate
+pub fn discover_amd_pstate() -> Result<Option<AmdPstate>> {
+    discover_amd_pstate_at(SYSFS_CPU_ROOT)
+}
+
+/// find the global amd-pstate settings in <root>/amd_pstate.
+/// Returns None on systems without amd-pstate.
+pub fn discover_amd_pstate_at<P: AsRef<Path>>(root: P) -> Result<Option<AmdPstate>> {
+    let dir = root.as_ref().join("amd_pstate");
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    AmdPstate::discover(dir).map(Some)
+}
+
+/// Global settings of the amd-pstate driver
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AmdPstate {
+    /// Path to the amd_pstate directory
+    dir: PathBuf,
+    /// Operation mode
+    status: AmdPstateStatus,
+    /// Whether the scheduler prefers the highest ranked cores, None on kernels without prefcore support
+    prefcore: Option<bool>,
+}
+
+impl AmdPstate {
+    /// discover the settings in the given amd_pstate directory
+    pub fn discover(dir: PathBuf) -> Result<AmdPstate> {
+        let status_file = dir.join("status");
+        let status = read_attr(&status_file)?.parse().map_err(|e| match e {
+            Error::InvalidInput { input, .. } => Error::Parse {
+                path: status_file,
+                content: input,
+            },
+            e => e,
+        })?;
+        let prefcore = optional(read_attr(&dir.join("prefcore")))?.map(|p| p == "enabled");
+
+        Ok(AmdPstate {
+            dir,
+            status,
+            prefcore,
+        })
+    }
+
+    /// returns the operation mode
+    pub fn status(&self) -> AmdPstateStatus {
+        self.status
+    }
+
+    /// returns whether preferred core support is enabled
+    pub fn prefcore(&self) -> Option<bool> {
+        self.prefcore
+    }
+
+    /// Switch the operation mode. Switching from or to active mode changes the scaling driver
+    /// and the available governors of all cores, so run discovery again afterwards.
+    pub fn set_status(&mut self, status: AmdPstateStatus) -> Result<()> {
+        debug!("Setting amd-pstate status {}", status);
+        write_attr(&self.dir.join("status"), status.as_str())
+    }
+}
+
+/// Per-core amd-pstate attributes as read from cpu<x>/cpufreq/amd_pstate_*
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmdPstateCore {
+    /// Highest abstract performance level of the core, differs between cores on CPUs with preferred cores
+    highest_perf: u32,
+    /// Lowest frequency before power efficiency drops non-linearly
+    lowest_nonlinear_freq: Frequency,
+    /// Maximum frequency including boost
+    max_freq: Frequency,
+    /// Preferred core ranking, higher is better; None without prefcore support
+    prefcore_ranking: Option<u32>,
+}
+
+impl AmdPstateCore {
+    /// discover the amd-pstate attributes in the cpufreq directory of a core.
+    /// Returns None if the core is not driven by amd-pstate.
+    pub(crate) fn discover(cpufreq: &Path) -> Result<Option<AmdPstateCore>> {
+        let highest_perf = match optional(read_num(&cpufreq.join("amd_pstate_highest_perf")))? {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+        let lowest_nonlinear_freq =
+            Frequency::from_khz(read_num(&cpufreq.join("amd_pstate_lowest_nonlinear_freq"))?);
+        let max_freq = Frequency::from_khz(read_num(&cpufreq.join("amd_pstate_max_freq"))?);
+        let prefcore_ranking = optional(read_num(&cpufreq.join("amd_pstate_prefcore_ranking")))?;
+
+        Ok(Some(AmdPstateCore {
+            highest_perf,
+            lowest_nonlinear_freq,
+            max_freq,
+            prefcore_ranking,
+        }))
+    }
+
+    /// returns the highest abstract performance level
+    pub fn highest_perf(&self) -> u32 {
+        self.highest_perf
+    }
+
+    /// returns the lowest frequency before power efficiency drops non-linearly
+    pub fn lowest_nonlinear_freq(&self) -> Frequency {
+        self.lowest_nonlinear_freq
+    }
+
+    /// returns the maximum frequency including boost
+    pub fn max_freq(&self) -> Frequency {
+        self.max_freq
+    }
+
+    /// returns the preferred core ranking, higher is better
+    pub fn prefcore_ranking(&self) -> Option<u32> {
+        self.prefcore_ranking
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{discover_amd_pstate_at, AmdPstateStatus};
+    use fake::{install_emulation, FakeSysfs};
+    use {discover_core_settings_at, Core, Driver, Frequency};
+
+    #[test]
+    fn amd_pstate() {
+        install_emulation();
+        let fake = FakeSysfs::builder()
+            .amd_pstate("active")
+            .core_prefcore_ranking(2, 236)
+            .build()
+            .unwrap();
+        let cores = discover_core_settings_at(fake.root()).unwrap();
+        assert_eq!(cores[0].driver(), &Driver::AmdPstateEpp);
+        let a = cores[2].amd_pstate().unwrap();
+        assert_eq!(a.prefcore_ranking(), Some(236));
+        assert_eq!(a.highest_perf(), 236);
+        assert_eq!(a.max_freq(), Frequency::from_khz(3_000_000));
+        assert_eq!(a.lowest_nonlinear_freq(), Frequency::from_khz(1_900_000));
+
+        let mut p = discover_amd_pstate_at(fake.root()).unwrap().unwrap();
+        assert_eq!(p.status(), AmdPstateStatus::Active);
+        assert_eq!(p.prefcore(), Some(true));
+        p.set_status(AmdPstateStatus::Guided).unwrap();
+        let c = Core::discover(fake.path("cpu0")).unwrap();
+        assert_eq!(c.driver(), &Driver::AmdPstate);
+        assert!(c.available_govs().contains(&"schedutil".to_string()));
+
+        p.set_status(AmdPstateStatus::Disable).unwrap();
+        let p = discover_amd_pstate_at(fake.root()).unwrap().unwrap();
+        assert_eq!(p.status(), AmdPstateStatus::Disable);
+
+        let fake = FakeSysfs::builder().build().unwrap();
+        assert!(discover_amd_pstate_at(fake.root()).unwrap().is_none());
+        let c = Core::discover(fake.path("cpu0")).unwrap();
+        assert!(c.amd_pstate().is_none());
+    }
+}
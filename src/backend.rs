@@ -0,0 +1,40 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Carries out the writes mediocore does to sysfs attributes.
+///
+/// [Sysfs] writes to the files directly and is used unless another backend is installed
+/// with [set_backend], e.g. the kernel emulation of the fake-sysfs feature.
+pub trait Backend: Send + Sync {
+    /// Write the value to the attribute, failing with the errno the kernel would return
+    fn write(&self, path: &Path, value: &str) -> io::Result<()>;
+}
+
+/// Writes straight to the attribute files, leaving all checks to the kernel
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sysfs;
+
+impl Backend for Sysfs {
+    fn write(&self, path: &Path, value: &str) -> io::Result<()> {
+        fs::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .and_then(|mut f| f.write_all(value.as_bytes()))
+    }
+}
+
+static BACKEND: OnceLock<&'static dyn Backend> = OnceLock::new();
+
+/// Install the backend carrying out all writes of this process.
+/// Can only be done once, returns the given backend if another one was installed before.
+pub fn set_backend(backend: &'static dyn Backend) -> Result<(), &'static dyn Backend> {
+    BACKEND.set(backend)
+}
+
+/// returns the installed backend or [Sysfs]
+pub(crate) fn backend() -> &'static dyn Backend {
+    BACKEND.get().cloned().unwrap_or(&Sysfs)
+}
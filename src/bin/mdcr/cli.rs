@@ -0,0 +1,1507 @@
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::io::{stdout, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{exit, Command};
+use std::time::Duration;
+use structopt::StructOpt;
+
+use mediocore::{
+    AmdPstateStatus, CapacityTier, Core, CoreKind, CpuSet, Discovery, Driver, EnergyPerfBias,
+    Error, Frequency, IdleState, IntelPstateStatus, LatencyHold, SkipReason, Snap, Thermal,
+    SYSFS_CPU_ROOT, SYSFS_THERMAL_ROOT,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "mediocore",
+    about = "discover and manipulate linux cpu frequency settings"
+)]
+struct Opt {
+    #[structopt(
+        long = "sysfs-root",
+        default_value = "/sys/devices/system/cpu",
+        parse(from_os_str)
+    )]
+    /// Directory to use instead of /sys/devices/system/cpu for all reads and writes.
+    sysfs_root: PathBuf,
+    #[structopt(long = "thermal-root", parse(from_os_str))]
+    /// Directory to use instead of /sys/class/thermal for show --thermal.
+    /// Required along with --sysfs-root, so the readings belong to the same system.
+    thermal_root: Option<PathBuf>,
+    #[structopt(subcommand)]
+    cmd: Mdcr,
+}
+
+#[derive(Debug, StructOpt)]
+enum Mdcr {
+    #[structopt(name = "set", alias = "s")]
+    /// Shortcut: s; Manipulate scaling governor and min/max scaling frequency. Run "mdcr set help" for details.
+    Set(Cfg),
+    #[structopt(name = "powersave", alias = "ps")]
+    /// Shortcut: ps; sets low and high scaling frequency thresholds to minimum and applies powersave governor.
+    Powersave,
+    #[structopt(name = "performance", alias = "p")]
+    /// Shortcut: p;  sets high scaling frequency threshold to maximum and applies performance governor.
+    Performance,
+    #[structopt(name = "online")]
+    /// Bring the given cores back online and show the resulting settings.
+    Online {
+        #[structopt(short = "c", long = "cores")]
+        /// Cores to bring online, e.g. 2-3,6.
+        cores: CpuSet,
+    },
+    #[structopt(name = "offline")]
+    /// Take the given cores offline and show the resulting settings. The last online core and cores which cannot be hot-plugged are refused.
+    Offline {
+        #[structopt(short = "c", long = "cores")]
+        /// Cores to take offline, e.g. 2-3,6.
+        cores: CpuSet,
+    },
+    #[structopt(name = "boost")]
+    /// Switch turbo/boost frequencies on or off, or show whether they are enabled.
+    Boost(BoostCmd),
+    #[structopt(name = "intel-pstate")]
+    /// Show or change the global intel_pstate settings, which limit all cores on top of their scaling limits.
+    IntelPstate(PstateCfg),
+    #[structopt(name = "amd-pstate")]
+    /// Show the amd-pstate mode or switch it.
+    AmdPstate {
+        /// Switch the operation mode: active (amd-pstate-epp), passive or guided (amd-pstate with the generic governors), or disable.
+        #[structopt(long = "status")]
+        status: Option<AmdPstateStatus>,
+    },
+    #[structopt(name = "pin")]
+    /// Pin cores to a fixed frequency through the userspace governor, e.g. for reproducible benchmarks.
+    Pin(PinCfg),
+    #[structopt(name = "stats")]
+    /// Show how long the cores spent at each frequency since boot or the last reset, e.g. to check a powersave profile.
+    Stats {
+        #[structopt(short = "c", long = "cores")]
+        /// Only show the policies governing the given cores, e.g. 0-3,8.
+        cores: Option<CpuSet>,
+        /// Reset the statistics after showing them.
+        #[structopt(long = "reset")]
+        reset: bool,
+    },
+    #[structopt(name = "idle")]
+    /// Show the idle states (C-states) of the cores or disable deep ones to lower wakeup latency.
+    Idle(IdleCmd),
+    #[structopt(name = "latency")]
+    /// Keep all cores out of idle states slower to leave than --max-us while running a command,
+    /// e.g. mdcr latency --max-us 10 -- ./benchmark. Nothing persists after the command exits.
+    Latency {
+        /// Longest acceptable wakeup latency in microseconds, 0 allows polling only.
+        #[structopt(long = "max-us")]
+        max_us: u32,
+        /// PM QoS device to hold instead of /dev/cpu_dma_latency.
+        #[structopt(
+            long = "device",
+            default_value = "/dev/cpu_dma_latency",
+            parse(from_os_str)
+        )]
+        device: PathBuf,
+        /// Command to run while the latency is held, after --.
+        #[structopt(raw(required = "true", last = "true"))]
+        command: Vec<String>,
+    },
+    #[structopt(name = "tune")]
+    /// Show or change the tunables of a governor in use, e.g. mdcr tune ondemand up_threshold=90 io_is_busy=1.
+    Tune {
+        /// Governor whose tunables to show or change: ondemand, conservative or schedutil.
+        governor: String,
+        /// Tunables to set as name=value. Without any the current values are shown.
+        #[structopt(parse(try_from_str = "parse_tunable"))]
+        tunables: Vec<(String, u32)>,
+    },
+    #[structopt(name = "show")]
+    /// Discover and show per-core settings either as console-friendly table or print the raw data as json via --json
+    Show {
+        #[structopt(long = "json", help = "print raw data as json")]
+        json: bool,
+        #[structopt(long = "by-package", help = "group the table by physical package")]
+        by_package: bool,
+        /// Also show thermal zones and cooling devices, e.g. to tell whether cores are throttled.
+        /// With --json the output becomes an object holding the cores and the thermal readings.
+        #[structopt(long = "thermal")]
+        thermal: bool,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum BoostCmd {
+    #[structopt(name = "on")]
+    /// Enable turbo/boost frequencies.
+    On,
+    #[structopt(name = "off")]
+    /// Disable turbo/boost frequencies, e.g. for reproducible benchmarks.
+    Off,
+    #[structopt(name = "status")]
+    /// Show whether turbo/boost frequencies are enabled.
+    Status,
+}
+
+#[derive(Debug, StructOpt)]
+struct PstateCfg {
+    /// Switch the operation mode: active (intel_pstate governs), passive (intel_cpufreq with the generic governors) or off.
+    #[structopt(long = "status")]
+    pub status: Option<IntelPstateStatus>,
+    /// Lower performance limit in percent of the maximum performance.
+    #[structopt(long = "min-perf-pct")]
+    pub min_perf_pct: Option<u32>,
+    /// Upper performance limit in percent of the maximum performance.
+    #[structopt(long = "max-perf-pct")]
+    pub max_perf_pct: Option<u32>,
+    /// Let HWP raise the minimum performance on IO wakeups: on or off.
+    #[structopt(long = "hwp-dynamic-boost", parse(try_from_str = "parse_switch"))]
+    pub hwp_dynamic_boost: Option<bool>,
+}
+
+#[derive(Debug, StructOpt)]
+enum IdleCmd {
+    #[structopt(name = "show")]
+    /// Show the idle states with their latency, usage and the cores they are disabled on.
+    Show {
+        #[structopt(short = "c", long = "cores")]
+        /// Only show the given cores, e.g. 0-3,8.
+        cores: Option<CpuSet>,
+    },
+    #[structopt(name = "disable")]
+    /// Disable idle states, e.g. mdcr idle disable --state C6 -c 0-3.
+    Disable(IdleCfg),
+    #[structopt(name = "enable")]
+    /// Enable previously disabled idle states.
+    Enable(IdleCfg),
+}
+
+#[derive(Debug, StructOpt)]
+struct IdleCfg {
+    /// Idle state by name or index, e.g. C6 or 3. Repeat to change several states.
+    #[structopt(
+        short = "s",
+        long = "state",
+        raw(required = "true", number_of_values = "1")
+    )]
+    pub states: Vec<String>,
+    #[structopt(short = "c", long = "cores")]
+    /// Cores to change in cpulist format, e.g. 0-3,8. If unspecified all cores are changed.
+    pub cores: Option<CpuSet>,
+}
+
+#[derive(Debug, StructOpt)]
+struct PinCfg {
+    /// Frequency to pin the cores to, e.g. 2GHz. Must be one of the frequency steps if the driver has them.
+    pub freq: Frequency,
+    #[structopt(short = "c", long = "cores")]
+    /// Cores to pin in cpulist format, e.g. 0-3,8. If unspecified all cores are pinned.
+    pub cores: Option<CpuSet>,
+    /// Round the frequency to the frequency steps supported by the cores: nearest, up or down.
+    #[structopt(long = "snap")]
+    pub snap: Option<Snap>,
+    /// Pin the selected cores even if they share a cpufreq policy with unselected cores,
+    /// which will then be pinned as well.
+    #[structopt(long = "force")]
+    pub force: bool,
+}
+
+fn parse_switch(s: &str) -> Result<bool, String> {
+    match s {
+        "on" | "1" => Ok(true),
+        "off" | "0" => Ok(false),
+        _ => Err(format!("expected on or off, got {}", s)),
+    }
+}
+
+fn parse_tunable(s: &str) -> Result<(String, u32), String> {
+    let mut parts = s.splitn(2, '=');
+    match (parts.next(), parts.next().map(|v| v.trim().parse())) {
+        (Some(name), Some(Ok(value))) if !name.is_empty() => Ok((name.trim().into(), value)),
+        _ => Err(format!(
+            "expected name=value like up_threshold=90, got {}",
+            s
+        )),
+    }
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "performance")]
+struct Cfg {
+    #[structopt(short = "g", long = "governor")]
+    /// Apply the provided scaling governor.
+    pub governor: Option<String>,
+    /// Change the low/min scaling frequency threshold, e.g. 800MHz or 1.2GHz.
+    #[structopt(short = "l", long = "low")]
+    pub low: Option<Frequency>,
+    /// Change the high/max scaling frequency threshold, e.g. 2.4GHz or 3000MHz.
+    #[structopt(short = "h", long = "high")]
+    pub high: Option<Frequency>,
+    /// Apply the energy performance preference, a name like balance_power or a raw value 0-255 (intel_pstate only)
+    /// (0 favours performance). Applied after the governor.
+    #[structopt(short = "e", long = "epp")]
+    pub epp: Option<String>,
+    /// Apply the Intel energy performance bias: 0-15 or performance, balance-performance, normal,
+    /// balance-power or power.
+    #[structopt(long = "epb")]
+    pub epb: Option<EnergyPerfBias>,
+    /// Round -l/-h to the frequency steps supported by the cores: nearest, up or down.
+    /// Without it, values between steps are written as given and the kernel rounds them.
+    #[structopt(long = "snap")]
+    pub snap: Option<Snap>,
+    #[structopt(short = "c", long = "cores")]
+    /// Cores to apply the settings to in cpulist format, e.g. 0-3,8. If unspecified settings are applied to all cores.
+    pub cores: Option<CpuSet>,
+    /// Only apply the settings to cores in the given physical package (socket).
+    #[structopt(long = "package")]
+    pub package: Option<i32>,
+    /// Only apply the settings to cores on the given NUMA node.
+    #[structopt(long = "node")]
+    pub node: Option<u32>,
+    /// Only apply the settings to the logical cores of the given physical core id.
+    /// Physical core ids repeat per package, so combine with --package on multi-socket systems.
+    #[structopt(long = "physical-core")]
+    pub physical_core: Option<i32>,
+    /// Only apply the settings to performance or efficiency cores of hybrid CPUs.
+    #[structopt(long = "kind")]
+    pub kind: Option<CoreKind>,
+    /// Only apply the settings to the little, medium or big cores of heterogeneous ARM CPUs, e.g. to cap the big cluster.
+    #[structopt(long = "tier")]
+    pub tier: Option<CapacityTier>,
+    /// Apply the settings even if the selected cores share a cpufreq policy with unselected cores,
+    /// which will then be changed as well.
+    #[structopt(long = "force")]
+    pub force: bool,
+}
+
+impl Cfg {
+    /// Whether any option narrows down the cores to apply the settings to
+    fn is_selective(&self) -> bool {
+        self.cores.is_some()
+            || self.package.is_some()
+            || self.node.is_some()
+            || self.physical_core.is_some()
+            || self.kind.is_some()
+            || self.tier.is_some()
+    }
+
+    /// Whether the settings are to be applied to the core. Cores without topology information never match topology filters.
+    fn selects(&self, core: &Core) -> bool {
+        let t = core.topology();
+        let package = t.map(|t| t.package());
+        let node = t.and_then(|t| t.node());
+        let physical_core = t.map(|t| t.core());
+
+        self.cores.as_ref().is_none_or(|c| c.contains(core.num()))
+            && (self.package.is_none() || self.package == package)
+            && (self.node.is_none() || self.node == node)
+            && (self.physical_core.is_none() || self.physical_core == physical_core)
+            && (self.kind.is_none() || self.kind == Some(core.kind()))
+            && (self.tier.is_none() || self.tier == core.tier())
+    }
+}
+
+macro_rules! try_or_exit{
+    ($x:expr, $msg:expr) => (match $x{
+    	Ok(o) => o,
+        Err(Error::PermissionDenied{ref path}) =>{
+            eprintln!("{}", $msg);
+            eprintln!("Error: Permission denied.\nDo you have write access to {}?", path.display());
+            exit(13)
+        },
+        Err(ref e @ Error::OutOfRange{..}) | Err(ref e @ Error::FrequencyOutOfRange{..}) | Err(ref e @ Error::UnknownGovernor{..}) | Err(ref e @ Error::Rejected{..}) | Err(ref e @ Error::InvalidInput{..}) =>{
+            eprintln!("{}", $msg);
+            eprintln!("Error: Invalid input.\n\tCause: {}.\nPlease check arguments.",e);
+            exit(22)
+        },
+        Err(ref e @ Error::LastOnlineCpu{..}) =>{
+            eprintln!("{}", $msg);
+            eprintln!("Error: Refused.\n\tCause: {}.",e);
+            exit(16)
+        },
+        Err(ref e @ Error::NotSupported{..}) =>{
+            eprintln!("{}", $msg);
+            eprintln!("Error: Not supported.\n\tCause: {}.\nDoes your kernel/cpufreq driver provide this setting?",e);
+            exit(95)
+        },
+    	Err(e) => {
+            eprintln!("{}", $msg);
+    		eprintln!("Error: Unexpected Error:\n\tCause {}.",e);
+    		exit(1)
+    	}
+    })
+}
+
+fn discover(root: &Path) -> Discovery {
+    let d = try_or_exit!(mediocore::discover_at(root), "Failed to discover cores");
+    debug!("Discovered Configuration {:#?}", d);
+    d
+}
+
+fn discover_cores(root: &Path) -> Vec<Core> {
+    discover(root).into_cores()
+}
+
+fn powersave(root: &Path) {
+    let mut cores = discover_cores(root);
+
+    {
+        let cores_no_psave = cores
+            .iter()
+            .filter(|c| {
+                !c.available_govs()
+                    .iter()
+                    .any(|g: &String| "powersave".eq(g))
+            })
+            .map(|c| c.num())
+            .collect::<Vec<_>>();
+        if !cores_no_psave.is_empty() {
+            eprintln!(
+                "Cores {:?} do not support the powersave governor.",
+                cores_no_psave
+            );
+            exit(1);
+        }
+    }
+
+    debug!("Applying powersave governor on all cores");
+
+    let res = cores
+        .iter_mut()
+        .try_for_each(|c| c.set_governor("powersave"));
+
+    try_or_exit!(res, "Failed to set powersave governor");
+    set_epp_if_available(&mut cores, "power");
+
+    // set frequency to minimum
+    let res = cores.iter_mut().try_for_each(|c| {
+        let min = c.cpu_min();
+        c.set_min(min).and(c.set_max(min))
+    });
+
+    try_or_exit!(res, "Failed to set scaling frequency to minimum");
+    exit(0)
+}
+
+fn performance(root: &Path) {
+    let mut cores = discover_cores(root);
+
+    {
+        let cores_no_perf = cores
+            .iter()
+            .filter(|c| {
+                !c.available_govs()
+                    .iter()
+                    .any(|g: &String| "performance".eq(g))
+            })
+            .map(|c| c.num())
+            .collect::<Vec<_>>();
+        if !cores_no_perf.is_empty() {
+            eprintln!(
+                "Cores {:?} do not support the performance governor.",
+                cores_no_perf
+            );
+            exit(1);
+        }
+    }
+
+    debug!("Applying performance governor on all cores");
+
+    let res = cores
+        .iter_mut()
+        .try_for_each(|c| c.set_governor("performance"));
+
+    try_or_exit!(res, "Failed to set performance governor");
+    set_epp_if_available(&mut cores, "performance");
+
+    // set frequency to minimum
+    let res = cores.iter_mut().try_for_each(|c| {
+        let max = c.cpu_max();
+        c.set_max(max)
+    });
+
+    try_or_exit!(res, "Failed to set scaling frequency to maximum");
+
+    // intel_pstate caps all cores on top of their scaling limits
+    let pstate = try_or_exit!(
+        mediocore::discover_intel_pstate_at(root),
+        "Failed to discover intel_pstate settings"
+    );
+    if let Some(pct) = pstate.and_then(|p| p.max_perf_pct()).filter(|p| *p < 100) {
+        eprintln!(
+            "Warning: intel_pstate limits all cores to {}% of their maximum performance. Use \"mdcr intel-pstate --max-perf-pct 100\" to lift it.",
+            pct
+        );
+    }
+    exit(0)
+}
+
+/// Apply the energy performance preference on the cores offering it, as it matters more than
+/// the governor with HWP or amd-pstate in active mode
+fn set_epp_if_available(cores: &mut [Core], epp: &str) {
+    let res = cores
+        .iter_mut()
+        .filter(|c| c.available_epps().iter().any(|e| e == epp))
+        .try_for_each(|c| c.set_epp(epp));
+    try_or_exit!(
+        res,
+        format!("Failed to set energy performance preference to {}", epp)
+    );
+}
+
+/// Round freq to the frequency steps of the core if requested.
+/// Otherwise warn if the kernel is going to round it in direction of kernel_snap.
+fn snap(core: &Core, freq: Frequency, snap: Option<Snap>, kernel_snap: Snap) -> Frequency {
+    match snap {
+        Some(s) => core.snap(freq, s),
+        None => {
+            let applied = core.snap(freq, kernel_snap);
+            if applied != freq {
+                eprintln!(
+                    "Warning: Core {} only supports discrete frequency steps, {} will be applied instead of {}. Use --snap to choose the rounding.",
+                    core.num(),
+                    applied,
+                    freq
+                );
+            }
+            freq
+        }
+    }
+}
+
+/// Settings apply to whole policies, so refuse (or warn if forced) when the selection splits a policy
+fn check_policies(d: &Discovery, selection: &CpuSet, force: bool) {
+    let mut split = false;
+    for (p, _) in d.policies() {
+        let siblings = p.unselected_siblings(selection);
+        if !siblings.is_empty() {
+            split = true;
+            eprintln!(
+                "{}: Policy {} also governs cores {}, which will be changed as well.",
+                if force { "Warning" } else { "Error" },
+                p.num(),
+                siblings
+            );
+        }
+    }
+
+    if split && !force {
+        eprintln!("Add those cores to -c or pass --force to apply the settings anyway.");
+        exit(1);
+    }
+}
+
+fn set(root: &Path, cfg: Cfg) {
+    let d = discover(root);
+    let mut cores = d.cores().to_vec();
+
+    if cfg.governor.is_none()
+        && cfg.low.is_none()
+        && cfg.high.is_none()
+        && cfg.epp.is_none()
+        && cfg.epb.is_none()
+    {
+        eprintln!("Please provide settings to set. Run \"mdcr help set\" to see the options");
+        exit(1);
+    }
+
+    // cores specified? well then drop the others
+    if cfg.is_selective() {
+        cores.retain(|c| cfg.selects(c));
+        if cores.is_empty() {
+            eprintln!("No cores match the selection.");
+            exit(1);
+        }
+        let selection = cores.iter().map(|c| c.num()).collect::<CpuSet>();
+        check_policies(&d, &selection, cfg.force);
+    }
+
+    let snap_to = cfg.snap;
+
+    match cfg.governor {
+        Some(gov) => {
+            info!("Setting governor");
+            let res = cores.iter_mut().try_for_each(|c| c.set_governor(&gov));
+            try_or_exit!(res, format!("Failed to set governor to {}", gov));
+        }
+        None => debug!("No governor settings to apply"),
+    };
+
+    match cfg.epp {
+        Some(epp) => {
+            info!("Setting energy performance preference");
+            let res = cores.iter_mut().try_for_each(|c| {
+                let epp = c.validate_epp(&epp)?;
+                c.set_epp(epp)
+            });
+            try_or_exit!(
+                res,
+                format!("Failed to set energy performance preference to {}", epp)
+            );
+        }
+        None => debug!("No energy performance preference to apply"),
+    };
+
+    match cfg.epb {
+        Some(epb) => {
+            info!("Setting energy performance bias");
+            let res = cores.iter_mut().try_for_each(|c| c.set_epb(epb));
+            try_or_exit!(
+                res,
+                format!("Failed to set energy performance bias to {}", epb)
+            );
+        }
+        None => debug!("No energy performance bias to apply"),
+    };
+
+    match cfg.low {
+        Some(min) => {
+            info!("Setting minimum frequencies");
+            let res = cores.iter_mut().try_for_each(|c| {
+                let min = snap(c, min, snap_to, Snap::Up);
+                c.set_min(min)
+            });
+            try_or_exit!(res, format!("Failed to set minimum frequency to {}", min));
+        }
+        None => debug!("No min settings to apply"),
+    };
+
+    match cfg.high {
+        Some(max) => {
+            info!("Setting maximum frequencies");
+            let res = cores.iter_mut().try_for_each(|c| {
+                let max = snap(c, max, snap_to, Snap::Down);
+                c.set_max(max)
+            });
+            try_or_exit!(res, format!("Failed to set maximum frequency to {}", max));
+        }
+        None => debug!("No max settings to apply"),
+    };
+
+    exit(0)
+}
+
+fn pin(root: &Path, cfg: PinCfg) {
+    let d = discover(root);
+    let mut cores = d.cores().to_vec();
+
+    if let Some(ref selection) = cfg.cores {
+        cores.retain(|c| selection.contains(c.num()));
+        if cores.is_empty() {
+            eprintln!("No cores match the selection.");
+            exit(1);
+        }
+        let selection = cores.iter().map(|c| c.num()).collect::<CpuSet>();
+        check_policies(&d, &selection, cfg.force);
+    }
+
+    // check everything up front so we do not stop halfway
+    for c in cores.iter() {
+        if c.curr_gov() != "userspace" && c.validate_governor("userspace").is_err() {
+            eprintln!(
+                "Core {} cannot be pinned: the {} driver does not offer the userspace governor (available: {}).",
+                c.num(),
+                c.driver(),
+                c.available_govs().join(", ")
+            );
+            match *c.driver() {
+                Driver::IntelPstate => eprintln!(
+                    "Switch intel_pstate to passive mode first: mdcr intel-pstate --status passive"
+                ),
+                Driver::AmdPstateEpp => eprintln!(
+                    "Switch amd-pstate to passive mode first: mdcr amd-pstate --status passive"
+                ),
+                _ => (),
+            }
+            exit(95);
+        }
+        let freq = cfg.snap.map_or(cfg.freq, |s| c.snap(cfg.freq, s));
+        try_or_exit!(
+            c.validate_speed(freq),
+            format!("Cannot pin core {} to {}", c.num(), freq)
+        );
+        if freq < c.curr_min() || freq > c.curr_max() {
+            eprintln!(
+                "Warning: Core {} is limited to {}-{}, the kernel will clamp {} to that range.",
+                c.num(),
+                c.curr_min(),
+                c.curr_max(),
+                freq
+            );
+        }
+    }
+
+    for c in cores.iter_mut() {
+        if c.curr_gov() != "userspace" {
+            try_or_exit!(
+                c.set_governor("userspace"),
+                format!("Failed to apply the userspace governor on core {}", c.num())
+            );
+        }
+        let freq = cfg.snap.map_or(cfg.freq, |s| c.snap(cfg.freq, s));
+        try_or_exit!(
+            c.set_speed(freq),
+            format!("Failed to pin core {} to {}", c.num(), freq)
+        );
+    }
+
+    let d = discover(root);
+    print_pretty(d.cores(), false);
+    print_skipped(&d);
+    exit(0)
+}
+
+fn hotplug(root: &Path, cores: &CpuSet, online: bool) {
+    // check everything up front so we do not stop halfway
+    let not_pluggable = cores
+        .iter()
+        .filter(|c| !mediocore::is_hotpluggable_at(root, *c))
+        .collect::<CpuSet>();
+    if !not_pluggable.is_empty() {
+        eprintln!("Cores {} cannot be hot-plugged.", not_pluggable);
+        exit(1);
+    }
+
+    if !online {
+        let d = discover(root);
+        // without the online list of the kernel, go by the cores which were discovered online
+        let mut up = d.online().clone();
+        if up.is_empty() {
+            up = d.cores().iter().map(|c| c.num()).collect();
+        }
+        if up.difference(cores).is_empty() {
+            eprintln!("Refusing to take the last online cores offline.");
+            exit(1);
+        }
+    }
+
+    for c in cores {
+        try_or_exit!(
+            mediocore::set_online_at(root, c, online),
+            format!(
+                "Failed to bring core {} {}",
+                c,
+                if online { "online" } else { "offline" }
+            )
+        );
+    }
+
+    // cores coming back online need to be discovered again
+    let d = discover(root);
+    print_pretty(d.cores(), false);
+    print_skipped(&d);
+    exit(0)
+}
+
+fn print_pretty(cores: &[Core], by_package: bool) {
+    println!("Current Settings:");
+    // e.g. VMs without cpufreq, callers list the skipped entries
+    if cores.is_empty() {
+        println!("No cpufreq-capable cores");
+        return;
+    }
+
+    // hybrid CPUs get separate tables for performance and efficiency cores
+    let hybrid = cores.iter().any(|c| c.kind() != CoreKind::Unknown);
+    if by_package || hybrid {
+        let group_of = |c: &Core| {
+            (
+                if by_package {
+                    c.topology().map(|t| t.package())
+                } else {
+                    None
+                },
+                c.kind(),
+            )
+        };
+        let mut groups = cores.iter().map(&group_of).collect::<Vec<_>>();
+        groups.sort();
+        groups.dedup();
+
+        for g in groups {
+            let group = cores
+                .iter()
+                .filter(|c| group_of(c) == g)
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut heading = vec![];
+            if by_package {
+                heading.push(match g.0 {
+                    Some(p) => format!("Package {}", p),
+                    None => "Unknown Package".into(),
+                });
+            }
+            if hybrid {
+                heading.push(match g.1 {
+                    CoreKind::Performance => "Performance Cores".into(),
+                    CoreKind::Efficiency => "Efficiency Cores".into(),
+                    CoreKind::Unknown => "Unknown Cores".into(),
+                });
+            }
+            println!("{}:", heading.join(", "));
+            print_table(&group);
+        }
+    } else {
+        print_table(cores);
+    }
+
+    print_available(cores);
+}
+
+fn print_table(cores: &[Core]) {
+    /// Expected terminal line length
+    const TERM_LEN: usize = 80;
+    /// Width of the table
+    const TABLE_LEGEND_LEN: usize = 23;
+
+    // find out how long the governor and driver descriptions are, then scale space alotted to each core accordingly
+    let longest_desc = cores
+        .iter()
+        .map(|c| {
+            c.curr_gov()
+                .len()
+                .max(c.driver().as_str().len())
+                .max(c.epp().map_or(0, |e| e.len()))
+                .max(c.epb().map_or(0, |e| e.to_string().len()))
+        })
+        .max();
+    let longest_desc = match longest_desc {
+        Some(l) => l,
+        None => return,
+    };
+    // add 3 chars of padding (1 front, 1 end, 1 for the separator)
+    let per_core_chars = longest_desc + 3;
+    // at least one core per line, even if the descriptions exceed the terminal
+    let cores_per_line = ((TERM_LEN - TABLE_LEGEND_LEN) / per_core_chars).max(1);
+
+    // generate lines of core descriptions
+    for cs in cores.chunks(cores_per_line) {
+        let mut creline: String = "Core                   ".into();
+        let mut minline: String = "Min CPU/Current [GHz]  ".into();
+        let mut maxline: String = "Max CPU/Current [GHz]  ".into();
+        let mut curline: String = "Current [GHz]          ".into();
+        let mut pinline: String = "Pinned [GHz]           ".into();
+        let mut govline: String = "Current Governor       ".into();
+        let mut drvline: String = "Driver                 ".into();
+        let mut rnkline: String = "Prefcore Ranking       ".into();
+        let mut eppline: String = "Energy Perf Pref       ".into();
+        let mut epbline: String = "Energy Perf Bias       ".into();
+
+        for core in cs.iter() {
+            let pad_to = creline.len() + per_core_chars;
+            creline.push_str(&format!(" {}", core.num()));
+            minline.push_str(&format!(
+                " {:03.3}/{:03.3}",
+                core.cpu_min().as_ghz(),
+                core.curr_min().as_ghz()
+            ));
+            maxline.push_str(&format!(
+                " {:03.3}/{:03.3}",
+                core.cpu_max().as_ghz(),
+                core.curr_max().as_ghz()
+            ));
+            curline.push_str(&format!(" {:03.3}", core.curr_freq().as_ghz()));
+            if let Some(speed) = core.speed() {
+                pinline.push_str(&format!(" {:03.3}", speed.as_ghz()));
+            }
+            govline.push_str(&format!(" {}", core.curr_gov()));
+            drvline.push_str(&format!(" {}", core.driver()));
+            if let Some(e) = core.epp() {
+                eppline.push_str(&format!(" {}", e));
+            }
+            if let Some(e) = core.epb() {
+                epbline.push_str(&format!(" {}", e));
+            }
+            if let Some(r) = core.amd_pstate().and_then(|a| a.prefcore_ranking()) {
+                rnkline.push_str(&format!(" {}", r));
+            }
+            for line in [
+                &mut creline,
+                &mut minline,
+                &mut maxline,
+                &mut curline,
+                &mut pinline,
+                &mut govline,
+                &mut drvline,
+                &mut eppline,
+                &mut epbline,
+                &mut rnkline,
+            ]
+            .iter_mut()
+            {
+                while line.len() < pad_to {
+                    line.push(' ');
+                }
+                // not a normal vertical line but box drawing character U+2502
+                // https://en.wikipedia.org/wiki/Box-drawing_character
+                line.push('│');
+            }
+        }
+
+        println!("{}", creline);
+        println!("{}", minline);
+        println!("{}", maxline);
+        println!("{}", curline);
+        if cs.iter().any(|c| c.speed().is_some()) {
+            println!("{}", pinline);
+        }
+        println!("{}", govline);
+        println!("{}", drvline);
+        if cs.iter().any(|c| c.epp().is_some()) {
+            println!("{}", eppline);
+        }
+        if cs.iter().any(|c| c.epb().is_some()) {
+            println!("{}", epbline);
+        }
+        if cs
+            .iter()
+            .any(|c| c.amd_pstate().and_then(|a| a.prefcore_ranking()).is_some())
+        {
+            println!("{}", rnkline);
+        }
+
+        let mut divider = String::with_capacity(TERM_LEN);
+        (0..creline.len() - 8).for_each(|i| {
+            if i < TABLE_LEGEND_LEN {
+                divider.push(' ');
+            } else {
+                // not a normal dash but box drawing character U+2500
+                // also longer than normal
+                divider.push('─');
+            }
+        });
+        println!("{}", divider);
+    }
+}
+
+fn print_available(cores: &[Core]) {
+    // display all available governors
+    //OPT: maybe use color coding so we can show which core supports which (most likely they are not going to differ... so why bother with the terminal color crate)
+    let available_governors = cores.iter().fold(HashSet::new(), |mut govs, c| {
+        c.available_govs().iter().for_each(|g| {
+            let _ = govs.insert(g);
+        });
+        govs
+    });
+
+    let av_govs = available_governors.iter().fold(
+        "* Available Governors   ".to_string(),
+        |mut av_govs, gov| {
+            av_govs.push_str(&format!("{} ", gov));
+            av_govs
+        },
+    );
+
+    println!("{}", av_govs);
+
+    let mut available_freqs = cores
+        .iter()
+        .flat_map(|c| c.available_freqs().iter())
+        .collect::<Vec<_>>();
+    available_freqs.sort();
+    available_freqs.dedup();
+    if !available_freqs.is_empty() {
+        let av_freqs = available_freqs.iter().fold(
+            "* Available Frequencies ".to_string(),
+            |mut av_freqs, freq| {
+                av_freqs.push_str(&format!("{} ", freq));
+                av_freqs
+            },
+        );
+        println!("{}", av_freqs);
+    }
+    // all good? exit with 0
+}
+
+/// Cores sharing a policy change together, so list the policies which govern more than one core
+fn print_policies(d: &Discovery) {
+    for (p, cores) in d.policies() {
+        if cores.len() > 1 {
+            let nums = cores.iter().map(|c| c.num()).collect::<CpuSet>();
+            println!("* Policy {:<15} cores {}", p.num(), nums);
+        }
+    }
+}
+
+fn print_skipped(d: &Discovery) {
+    for s in d.skipped() {
+        let reason = match *s.reason() {
+            SkipReason::Offline => "offline",
+            SkipReason::NoCpufreq => "no cpufreq support",
+            SkipReason::Unparseable => "unparseable",
+            SkipReason::Failed(ref e) => e,
+        };
+        println!("* Skipped {}: {}", s.path().display(), reason);
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    let s = serde_json::to_string_pretty(value).expect("Serialisation failed");
+    if let Err(e) = stdout().write_all(s.as_ref()) {
+        eprintln!("Failed to write json to stdout: {}", e);
+        exit(1)
+    }
+}
+
+fn boost(root: &Path, cmd: BoostCmd) {
+    match cmd {
+        BoostCmd::On | BoostCmd::Off => {
+            let enabled = matches!(cmd, BoostCmd::On);
+            try_or_exit!(
+                mediocore::set_boost_at(root, enabled),
+                format!(
+                    "Failed to turn boost {}",
+                    if enabled { "on" } else { "off" }
+                )
+            );
+        }
+        BoostCmd::Status => (),
+    }
+
+    let enabled = try_or_exit!(
+        mediocore::boost_enabled_at(root),
+        "Failed to read boost state"
+    );
+    println!("Boost: {}", if enabled { "on" } else { "off" });
+    exit(0)
+}
+
+fn intel_pstate(root: &Path, cfg: PstateCfg) {
+    let mut pstate = match try_or_exit!(
+        mediocore::discover_intel_pstate_at(root),
+        "Failed to discover intel_pstate settings"
+    ) {
+        Some(p) => p,
+        None => {
+            eprintln!("intel_pstate is not available on this system.");
+            exit(95)
+        }
+    };
+
+    // switch the mode first, the other settings may only exist in the new one
+    if let Some(status) = cfg.status {
+        try_or_exit!(
+            pstate.set_status(status),
+            format!("Failed to switch intel_pstate to {}", status)
+        );
+    }
+    if let Some(pct) = cfg.max_perf_pct {
+        try_or_exit!(
+            pstate.set_max_perf_pct(pct),
+            format!("Failed to set max_perf_pct to {}", pct)
+        );
+    }
+    if let Some(pct) = cfg.min_perf_pct {
+        try_or_exit!(
+            pstate.set_min_perf_pct(pct),
+            format!("Failed to set min_perf_pct to {}", pct)
+        );
+    }
+    if let Some(enabled) = cfg.hwp_dynamic_boost {
+        try_or_exit!(
+            pstate.set_hwp_dynamic_boost(enabled),
+            "Failed to set hwp_dynamic_boost"
+        );
+    }
+
+    print_intel_pstate(root);
+    exit(0)
+}
+
+fn print_intel_pstate(root: &Path) {
+    // the per-core settings are shown already, so do not fail over the global ones
+    let p = match mediocore::discover_intel_pstate_at(root) {
+        Ok(Some(p)) => p,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Warning: Failed to discover intel_pstate settings: {}", e);
+            return;
+        }
+    };
+
+    let mut line = format!("* intel_pstate           {}", p.status());
+    if let (Some(min), Some(max)) = (p.min_perf_pct(), p.max_perf_pct()) {
+        line.push_str(&format!(", performance {}-{}%", min, max));
+    }
+    if let Some(boost) = p.hwp_dynamic_boost() {
+        line.push_str(&format!(
+            ", HWP dynamic boost {}",
+            if boost { "on" } else { "off" }
+        ));
+    }
+    println!("{}", line);
+}
+
+fn amd_pstate(root: &Path, status: Option<AmdPstateStatus>) {
+    let mut pstate = match try_or_exit!(
+        mediocore::discover_amd_pstate_at(root),
+        "Failed to discover amd-pstate settings"
+    ) {
+        Some(p) => p,
+        None => {
+            eprintln!("amd-pstate is not available on this system.");
+            exit(95)
+        }
+    };
+
+    if let Some(status) = status {
+        try_or_exit!(
+            pstate.set_status(status),
+            format!("Failed to switch amd-pstate to {}", status)
+        );
+    }
+
+    print_amd_pstate(root, &discover_cores(root));
+    exit(0)
+}
+
+fn print_amd_pstate(root: &Path, cores: &[Core]) {
+    // the per-core settings are shown already, so do not fail over the global ones
+    let pstate = match mediocore::discover_amd_pstate_at(root) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Warning: Failed to discover amd-pstate settings: {}", e);
+            None
+        }
+    };
+    if let Some(p) = pstate {
+        let mut line = format!("* amd-pstate             {}", p.status());
+        if let Some(prefcore) = p.prefcore() {
+            line.push_str(&format!(
+                ", prefcore {}",
+                if prefcore { "on" } else { "off" }
+            ));
+        }
+        println!("{}", line);
+    }
+
+    // best ranked cores first, e.g. to pin latency-critical work to them
+    let mut ranked = cores
+        .iter()
+        .filter_map(|c| {
+            c.amd_pstate()
+                .and_then(|a| a.prefcore_ranking())
+                .map(|r| (r, c.num()))
+        })
+        .collect::<Vec<_>>();
+    if !ranked.is_empty() {
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        let order = ranked.iter().fold(
+            "* Preferred Cores        ".to_string(),
+            |mut order, (_, c)| {
+                order.push_str(&format!("{} ", c));
+                order
+            },
+        );
+        println!("{}", order);
+    }
+}
+
+fn idle(root: &Path, cmd: IdleCmd) {
+    let (selection, change) = match cmd {
+        IdleCmd::Show { cores } => (cores, None),
+        IdleCmd::Disable(cfg) => (cfg.cores, Some((cfg.states, true))),
+        IdleCmd::Enable(cfg) => (cfg.cores, Some((cfg.states, false))),
+    };
+    let selected = |cores: Vec<Core>| -> Vec<Core> {
+        cores
+            .into_iter()
+            .filter(|c| selection.as_ref().is_none_or(|s| s.contains(c.num())))
+            .collect()
+    };
+
+    // the idle states of each selected core
+    let idle_states = |cores: &[Core]| -> Vec<(u32, Vec<IdleState>)> {
+        cores
+            .iter()
+            .map(|c| {
+                let states = try_or_exit!(
+                    c.idle_states(),
+                    format!("Failed to read the idle states of core {}", c.num())
+                );
+                (c.num(), states)
+            })
+            .collect()
+    };
+
+    let mut cores = selected(discover_cores(root));
+    if cores.is_empty() {
+        eprintln!("No cores match the selection.");
+        exit(1);
+    }
+    let mut current = idle_states(&cores);
+    if current.iter().all(|(_, s)| s.is_empty()) {
+        eprintln!("No idle states available, is cpuidle enabled in the kernel?");
+        exit(95);
+    }
+
+    if let Some((states, disable)) = change {
+        // check everything up front so we do not stop halfway
+        for (num, available) in current.iter() {
+            for state in states.iter() {
+                if !available.iter().any(|s| s.is(state)) {
+                    eprintln!(
+                        "Core {} has no idle state {}, it has {}.",
+                        num,
+                        state,
+                        available
+                            .iter()
+                            .map(|s| s.name())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    exit(22);
+                }
+            }
+        }
+        for c in cores.iter_mut() {
+            for state in states.iter() {
+                try_or_exit!(
+                    c.set_idle_state_disabled(state, disable),
+                    format!(
+                        "Failed to {} {} on core {}",
+                        if disable { "disable" } else { "enable" },
+                        state,
+                        c.num()
+                    )
+                );
+            }
+        }
+        current = idle_states(&cores);
+    }
+
+    print_idle(root, &current);
+    exit(0)
+}
+
+fn print_idle(root: &Path, cores: &[(u32, Vec<IdleState>)]) {
+    let cpuidle = try_or_exit!(
+        mediocore::discover_cpuidle_at(root),
+        "Failed to discover cpuidle settings"
+    );
+    if let Some(i) = cpuidle {
+        let mut line = format!(
+            "* cpuidle                {}, governor {}",
+            i.driver().unwrap_or("no driver"),
+            i.governor()
+        );
+        if !i.available_governors().is_empty() {
+            line.push_str(&format!(
+                " (available: {})",
+                i.available_governors().join(" ")
+            ));
+        }
+        println!("{}", line);
+    }
+
+    // the states are the same on all cores, so sum them up per state
+    let depth = cores.iter().map(|(_, s)| s.len()).max().unwrap_or(0);
+    println!(
+        "{:<8} {:>8} {:>10} {:>12} {:>13}  {:<12} Description",
+        "State", "Latency", "Residency", "Usage", "Time", "Disabled on"
+    );
+    for k in 0..depth {
+        let states = cores
+            .iter()
+            .filter_map(|(num, s)| s.get(k).map(|s| (*num, s)))
+            .collect::<Vec<_>>();
+        let first = states[0].1;
+        let disabled = states
+            .iter()
+            .filter(|(_, s)| s.disabled())
+            .map(|(c, _)| *c)
+            .collect::<CpuSet>();
+        println!(
+            "{:<8} {:>6}us {:>8}us {:>12} {:>13}  {:<12} {}",
+            first.name(),
+            first.latency(),
+            first.residency(),
+            states.iter().map(|(_, s)| s.usage()).sum::<u64>(),
+            format_duration(states.iter().map(|(_, s)| s.time()).sum()),
+            if disabled.is_empty() {
+                "-".to_string()
+            } else {
+                disabled.to_string()
+            },
+            first.desc()
+        );
+    }
+}
+
+fn latency(device: &Path, max_us: u32, command: &[String]) {
+    let hold = try_or_exit!(
+        LatencyHold::new_at(device, max_us),
+        format!("Failed to hold the wakeup latency at {}us", max_us)
+    );
+    info!("Holding wakeup latency at {}us", hold.max_us());
+
+    let status = match Command::new(&command[0]).args(&command[1..]).status() {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("Failed to run {}: {}", command[0], e);
+            exit(127)
+        }
+    };
+    // release the hold before exiting, exit does not run destructors
+    drop(hold);
+
+    // like a shell, report death by signal as 128 + signal
+    exit(
+        status
+            .code()
+            .or_else(|| status.signal().map(|s| 128 + s))
+            .unwrap_or(1),
+    )
+}
+
+/// Format a duration as hours, minutes and seconds, e.g. 8h 02m 13s
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{}h {:02}m {:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn stats(root: &Path, cores: Option<CpuSet>, reset: bool) {
+    /// Width of the histogram bars
+    const BAR_LEN: usize = 40;
+
+    let policies = try_or_exit!(
+        mediocore::discover_policies_at(root),
+        "Failed to discover cpufreq policies"
+    );
+    let mut shown = 0;
+
+    for p in policies.iter().filter(|p| {
+        cores
+            .as_ref()
+            .is_none_or(|c| !p.related_cpus().is_disjoint(c))
+    }) {
+        let mut stats = match p.stats() {
+            Ok(s) => s,
+            Err(Error::NotSupported { .. }) => {
+                eprintln!(
+                    "Policy {} (cores {}) has no statistics, the driver or kernel does not provide them.",
+                    p.num(),
+                    p.related_cpus()
+                );
+                continue;
+            }
+            Err(e) => try_or_exit!(
+                Err(e),
+                format!("Failed to read the statistics of policy {}", p.num())
+            ),
+        };
+        shown += 1;
+
+        let total = stats.total_time();
+        println!(
+            "Policy {} (cores {}): {} transitions in {}",
+            p.num(),
+            p.related_cpus(),
+            stats.total_trans(),
+            format_duration(total)
+        );
+        let mut residency = stats.time_in_state().to_vec();
+        residency.sort_by_key(|r| Reverse(r.freq()));
+        for r in residency {
+            let share = if total.as_millis() == 0 {
+                0.0
+            } else {
+                r.time().as_millis() as f64 / total.as_millis() as f64
+            };
+            let bar = (share * BAR_LEN as f64).round() as usize;
+            println!(
+                "  {:>6.3} GHz │{}{}│ {:>5.1}%  {}",
+                r.freq().as_ghz(),
+                "█".repeat(bar),
+                " ".repeat(BAR_LEN - bar),
+                share * 100.0,
+                format_duration(r.time())
+            );
+        }
+
+        if reset {
+            try_or_exit!(
+                stats.reset(),
+                format!("Failed to reset the statistics of policy {}", p.num())
+            );
+        }
+    }
+
+    if shown == 0 {
+        eprintln!("No cpufreq statistics available.");
+        exit(95);
+    }
+    if reset {
+        println!("Statistics reset.");
+    }
+    exit(0)
+}
+
+fn tune(root: &Path, governor: &str, tunables: &[(String, u32)]) {
+    let mut found = try_or_exit!(
+        mediocore::discover_tunables_at(root, governor),
+        format!("Failed to discover the tunables of {}", governor)
+    );
+    if found.is_empty() {
+        eprintln!(
+            "{} has no tunables or is not in use, apply it first with mdcr set -g {}",
+            governor, governor
+        );
+        exit(95)
+    }
+
+    // validate everything before writing anything
+    for t in found.iter() {
+        for (name, value) in tunables {
+            try_or_exit!(
+                t.validate(name, *value),
+                format!("Invalid value {} for {}", value, name)
+            );
+        }
+    }
+    for t in found.iter_mut() {
+        for (name, value) in tunables {
+            try_or_exit!(
+                t.set(name, *value),
+                format!("Failed to set {} to {}", name, value)
+            );
+        }
+    }
+
+    let found = try_or_exit!(
+        mediocore::discover_tunables_at(root, governor),
+        format!("Failed to discover the tunables of {}", governor)
+    );
+    for t in found {
+        match t.policy() {
+            Some(p) => println!("* {}, policy {}", t.governor(), p),
+            None => println!("* {}, all policies", t.governor()),
+        }
+        for (name, value) in t.tunables() {
+            println!("  {:<22} {}", name, value);
+        }
+    }
+    exit(0)
+}
+
+/// Format millidegrees Celsius, e.g. 62.5°C
+fn format_temp(temp: Option<i32>) -> String {
+    match temp {
+        Some(t) => format!("{:.1}°C", f64::from(t) / 1000.0),
+        None => "unreadable".into(),
+    }
+}
+
+fn print_thermal(thermal: &Thermal) {
+    if thermal.zones().is_empty() && thermal.cooling_devices().is_empty() {
+        println!("No thermal zones or cooling devices found.");
+        return;
+    }
+
+    if !thermal.zones().is_empty() {
+        println!("Thermal Zones:");
+    }
+    for z in thermal.zones() {
+        let mut line = format!("* {:<22} {}", z.kind(), format_temp(z.temp()));
+        if !z.trip_points().is_empty() {
+            let trips = z
+                .trip_points()
+                .iter()
+                .map(|t| format!("{} {}", t.kind(), format_temp(t.temp())))
+                .collect::<Vec<_>>();
+            line.push_str(&format!(" (trips: {})", trips.join(", ")));
+        }
+        println!("{}", line);
+    }
+
+    if !thermal.cooling_devices().is_empty() {
+        println!("Cooling Devices:");
+    }
+    let state = |s: Option<u32>| s.map_or("-".to_string(), |s| s.to_string());
+    for c in thermal.cooling_devices() {
+        println!(
+            "* {:<22} {}/{}{}",
+            c.kind(),
+            state(c.cur_state()),
+            state(c.max_state()),
+            if c.cur_state().is_some_and(|s| s > 0) {
+                " cooling"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+fn show(root: &Path, thermal_root: Option<&Path>, json: bool, by_package: bool, thermal: bool) {
+    let t = match (thermal, thermal_root) {
+        (false, _) => None,
+        (true, Some(dir)) => Some(try_or_exit!(
+            mediocore::discover_thermal_at(dir),
+            "Failed to discover thermal zones"
+        )),
+        (true, None) => {
+            eprintln!("Error: --thermal with a custom --sysfs-root requires --thermal-root.");
+            exit(22)
+        }
+    };
+    let d = discover(root);
+
+    if json {
+        match t {
+            Some(t) => print_json(&serde_json::json!({
+                "cores": d.cores(),
+                "thermal": t,
+            })),
+            None => print_json(&d.cores()),
+        }
+    } else {
+        print_pretty(d.cores(), by_package);
+        print_policies(&d);
+        print_intel_pstate(root);
+        print_amd_pstate(root, d.cores());
+        if let Some(t) = t {
+            print_thermal(&t);
+        }
+        print_skipped(&d);
+    }
+
+    exit(0);
+}
+
+pub fn main() {
+    let settings = Opt::from_args();
+    debug!("Args provided: {:#?}", settings);
+    let root = settings.sysfs_root.as_path();
+    // the host's thermal readings do not belong to a custom cpu root
+    let thermal_root = match settings.thermal_root {
+        Some(ref dir) => Some(dir.as_path()),
+        None if root == Path::new(SYSFS_CPU_ROOT) => Some(Path::new(SYSFS_THERMAL_ROOT)),
+        None => None,
+    };
+
+    match settings.cmd {
+        Mdcr::Set(c) => set(root, c),
+        Mdcr::Powersave => powersave(root),
+        Mdcr::Performance => performance(root),
+        Mdcr::Online { cores } => hotplug(root, &cores, true),
+        Mdcr::Offline { cores } => hotplug(root, &cores, false),
+        Mdcr::Boost(cmd) => boost(root, cmd),
+        Mdcr::IntelPstate(cfg) => intel_pstate(root, cfg),
+        Mdcr::AmdPstate { status } => amd_pstate(root, status),
+        Mdcr::Pin(cfg) => pin(root, cfg),
+        Mdcr::Stats { cores, reset } => stats(root, cores, reset),
+        Mdcr::Idle(cmd) => idle(root, cmd),
+        Mdcr::Latency {
+            max_us,
+            device,
+            command,
+        } => latency(&device, max_us, &command),
+        Mdcr::Tune { governor, tunables } => tune(root, &governor, &tunables),
+        Mdcr::Show {
+            json,
+            by_package,
+            thermal,
+        } => show(root, thermal_root, json, by_package, thermal),
+    };
+}
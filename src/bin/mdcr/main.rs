@@ -0,0 +1,13 @@
+#[macro_use]
+extern crate log;
+extern crate mediocore;
+extern crate serde;
+extern crate serde_json;
+extern crate structopt;
+
+// shared with the test harness in tests/support, which runs mdcr with the kernel emulation of mediocore::fake
+mod cli;
+
+fn main() {
+    cli::main()
+}
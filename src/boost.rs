@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use {read_num, write_attr, Error, Result, SYSFS_CPU_ROOT};
+
+/// Where the kernel lets us switch turbo/boost frequencies
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Source {
+    /// intel_pstate/no_turbo, inverted
+    NoTurbo(PathBuf),
+    /// cpufreq/boost, used by acpi-cpufreq and older amd-pstate
+    Global(PathBuf),
+    /// cpufreq/policy<x>/boost, used by newer amd-pstate
+    PerPolicy(Vec<PathBuf>),
+}
+
+/// Find the boost control in <root>, preferring the driver specific one
+fn source(root: &Path) -> Result<Source> {
+    let no_turbo = root.join("intel_pstate/no_turbo");
+    if no_turbo.is_file() {
+        return Ok(Source::NoTurbo(no_turbo));
+    }
+
+    let global = root.join("cpufreq/boost");
+    if global.is_file() {
+        return Ok(Source::Global(global));
+    }
+
+    let mut policies = fs::read_dir(root.join("cpufreq"))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_name().to_string_lossy().starts_with("policy"))
+                .map(|e| e.path().join("boost"))
+                .filter(|p| p.is_file())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if policies.is_empty() {
+        return Err(Error::NotSupported { path: global });
+    }
+    policies.sort();
+
+    Ok(Source::PerPolicy(policies))
+}
+
+/// returns whether turbo/boost frequencies are enabled for the cores in /sys/devices/system/cpu
+pub fn boost_enabled() -> Result<bool> {
+    boost_enabled_at(SYSFS_CPU_ROOT)
+}
+
+/// returns whether turbo/boost frequencies are enabled for the cores in <root>.
+/// With per-policy controls boost counts as enabled if any policy has it enabled.
+/// Fails with [Error::NotSupported] if the driver offers no boost control.
+pub fn boost_enabled_at<P: AsRef<Path>>(root: P) -> Result<bool> {
+    match source(root.as_ref())? {
+        Source::NoTurbo(path) => Ok(read_num(&path)? == 0),
+        Source::Global(path) => Ok(read_num(&path)? != 0),
+        Source::PerPolicy(paths) => {
+            for path in paths.iter() {
+                if read_num(path)? != 0 {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Enable or disable turbo/boost frequencies for the cores in /sys/devices/system/cpu
+pub fn set_boost(enabled: bool) -> Result<()> {
+    set_boost_at(SYSFS_CPU_ROOT, enabled)
+}
+
+/// Enable or disable turbo/boost frequencies for the cores in <root>,
+/// through intel_pstate/no_turbo, cpufreq/boost or the boost file of every policy.
+/// Fails with [Error::NotSupported] if the driver offers no boost control.
+pub fn set_boost_at<P: AsRef<Path>>(root: P, enabled: bool) -> Result<()> {
+    let value = if enabled { "1" } else { "0" };
+    debug!("Setting boost enabled: {}", enabled);
+
+    match source(root.as_ref())? {
+        Source::NoTurbo(path) => write_attr(&path, if enabled { "0" } else { "1" }),
+        Source::Global(path) => write_attr(&path, value),
+        Source::PerPolicy(paths) => paths.iter().try_for_each(|p| write_attr(p, value)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{boost_enabled_at, set_boost_at};
+    use fake::FakeSysfs;
+    use Error;
+
+    #[test]
+    fn boost() {
+        let fake = FakeSysfs::builder().boost(true).build().unwrap();
+        assert!(boost_enabled_at(fake.root()).unwrap());
+        set_boost_at(fake.root(), false).unwrap();
+        assert_eq!(fake.read("intel_pstate/no_turbo").unwrap(), "1");
+        assert!(!boost_enabled_at(fake.root()).unwrap());
+
+        let fake = FakeSysfs::builder()
+            .driver("acpi-cpufreq")
+            .boost(false)
+            .build()
+            .unwrap();
+        set_boost_at(fake.root(), true).unwrap();
+        assert_eq!(fake.read("cpufreq/boost").unwrap(), "1");
+
+        let fake = FakeSysfs::builder()
+            .driver("amd-pstate-epp")
+            .boost(true)
+            .build()
+            .unwrap();
+        set_boost_at(fake.root(), false).unwrap();
+        assert_eq!(fake.read("cpufreq/policy3/boost").unwrap(), "0");
+        assert!(!boost_enabled_at(fake.root()).unwrap());
+        fake.write("cpufreq/policy1/boost", "1").unwrap();
+        assert!(boost_enabled_at(fake.root()).unwrap());
+
+        let fake = FakeSysfs::builder().build().unwrap();
+        match boost_enabled_at(fake.root()) {
+            Err(Error::NotSupported { .. }) => (),
+            other => panic!("Unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use {optional, read_attr, read_num, write_attr, Error, Result, SYSFS_CPU_ROOT};
+
+/// find the global cpuidle settings in /sys/devices/system/cpu/cpuidle
+pub fn discover_cpuidle() -> Result<Option<CpuIdle>> {
+    discover_cpuidle_at(SYSFS_CPU_ROOT)
+}
+
+/// find the global cpuidle settings in <root>/cpuidle.
+/// Returns None on kernels without cpuidle.
+pub fn discover_cpuidle_at<P: AsRef<Path>>(root: P) -> Result<Option<CpuIdle>> {
+    let dir = root.as_ref().join("cpuidle");
+    let governor = match optional(read_attr(&dir.join("current_governor")))? {
+        Some(g) => g,
+        // older kernels only allow switching the governor with cpuidle_sysfs_switch
+        None => match optional(read_attr(&dir.join("current_governor_ro")))? {
+            Some(g) => g,
+            None => return Ok(None),
+        },
+    };
+    let driver = optional(read_attr(&dir.join("current_driver")))?;
+    let available_governors = optional(read_attr(&dir.join("available_governors")))?
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|g| g.into())
+        .collect();
+
+    Ok(Some(CpuIdle {
+        dir,
+        driver,
+        governor,
+        available_governors,
+    }))
+}
+
+/// Global cpuidle settings: the driver providing the idle states and the governor picking them
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CpuIdle {
+    /// Path to the cpuidle directory
+    dir: PathBuf,
+    /// The cpuidle driver, e.g. intel_idle or acpi_idle; None if there is none
+    driver: Option<String>,
+    /// The governor picking idle states, e.g. menu or teo
+    governor: String,
+    /// Governors to choose from, empty if the kernel does not allow switching
+    available_governors: Vec<String>,
+}
+
+impl CpuIdle {
+    /// returns the cpuidle driver
+    pub fn driver(&self) -> Option<&str> {
+        self.driver.as_deref()
+    }
+
+    /// returns the current governor
+    pub fn governor(&self) -> &str {
+        self.governor.as_ref()
+    }
+
+    /// returns the available governors
+    pub fn available_governors(&self) -> &[String] {
+        self.available_governors.as_ref()
+    }
+
+    /// Apply the given cpuidle governor after checking it against the available governors
+    pub fn set_governor(&mut self, governor: &str) -> Result<()> {
+        if !self.available_governors.iter().any(|g| g == governor) {
+            return Err(Error::UnknownGovernor {
+                governor: governor.into(),
+                available: self.available_governors.clone(),
+            });
+        }
+        debug!("Setting cpuidle governor {}", governor);
+        write_attr(&self.dir.join("current_governor"), governor)
+    }
+}
+
+/// An idle state (C-state) of a core as read from cpu<x>/cpuidle/state<k>.
+/// Deeper states save more power but take longer to leave.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdleState {
+    /// Path to the state directory
+    dir: PathBuf,
+    /// Index k of state<k>, 0 is the shallowest
+    index: u32,
+    /// Short name, e.g. C6
+    name: String,
+    /// Description as given by the driver
+    desc: String,
+    /// Exit latency in microseconds
+    latency: u32,
+    /// Target residency in microseconds, the minimum idle time for the state to pay off
+    residency: u32,
+    /// Number of times the state was entered
+    usage: u64,
+    /// Total time spent in the state
+    time: Duration,
+    /// Whether the state is disabled for this core
+    disabled: bool,
+}
+
+impl IdleState {
+    /// discover all idle states of the core specified by its path, shallowest first.
+    /// Empty if the core has no cpuidle directory.
+    pub(crate) fn discover_all(core: &Path) -> Result<Vec<IdleState>> {
+        let dir = core.join("cpuidle");
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => match Error::from_io(&dir, e) {
+                Error::NotSupported { .. } => return Ok(vec![]),
+                e => return Err(e),
+            },
+        };
+
+        let mut states = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                name.strip_prefix("state")
+                    .and_then(|k| k.parse().ok())
+                    .map(|k| (k, e.path()))
+            })
+            .map(|(k, dir)| IdleState::discover(dir, k))
+            .collect::<Result<Vec<_>>>()?;
+        states.sort_by_key(|s| s.index);
+
+        Ok(states)
+    }
+
+    /// discover the idle state in the given state<k> directory
+    pub fn discover(dir: PathBuf, index: u32) -> Result<IdleState> {
+        // usage and time overflow u32 on long running systems
+        let read_u64 = |attr: &str| -> Result<u64> {
+            let path = dir.join(attr);
+            let content = read_attr(&path)?;
+            content
+                .trim()
+                .parse()
+                .map_err(|_| Error::Parse { path, content })
+        };
+
+        let usage = read_u64("usage")?;
+        let time = Duration::from_micros(read_u64("time")?);
+        let name = read_attr(&dir.join("name"))?;
+        let desc = optional(read_attr(&dir.join("desc")))?.unwrap_or_default();
+        let latency = read_num(&dir.join("latency"))?;
+        let residency = optional(read_num(&dir.join("residency")))?.unwrap_or_default();
+        let disabled = optional(read_num(&dir.join("disable")))?.is_some_and(|d| d != 0);
+
+        Ok(IdleState {
+            dir,
+            index,
+            name,
+            desc,
+            latency,
+            residency,
+            usage,
+            time,
+            disabled,
+        })
+    }
+
+    /// returns the index k of state<k>, 0 is the shallowest
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// returns the short name, e.g. C6
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    /// returns the description as given by the driver
+    pub fn desc(&self) -> &str {
+        self.desc.as_ref()
+    }
+
+    /// returns the exit latency in microseconds
+    pub fn latency(&self) -> u32 {
+        self.latency
+    }
+
+    /// returns the target residency in microseconds
+    pub fn residency(&self) -> u32 {
+        self.residency
+    }
+
+    /// returns how often the state was entered
+    pub fn usage(&self) -> u64 {
+        self.usage
+    }
+
+    /// returns the total time spent in the state
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// returns whether the state is disabled
+    pub fn disabled(&self) -> bool {
+        self.disabled
+    }
+
+    /// Whether the state is the given one, by name (case insensitive) or index as in "C6", "3" or "state3"
+    pub fn is(&self, state: &str) -> bool {
+        let state = state.trim();
+        self.name.eq_ignore_ascii_case(state)
+            || state.strip_prefix("state").unwrap_or(state) == self.index.to_string()
+    }
+
+    pub(crate) fn set_disabled(&mut self, disabled: bool) -> Result<()> {
+        debug!(
+            "Setting {} disabled to {} in {:?}",
+            self.name, disabled, self.dir
+        );
+        write_attr(&self.dir.join("disable"), if disabled { "1" } else { "0" })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::discover_cpuidle_at;
+    use fake::FakeSysfs;
+    use std::fs;
+    use std::time::Duration;
+    use {discover_core_settings_at, Core, Error};
+
+    #[test]
+    fn cpuidle() {
+        let fake = FakeSysfs::builder().cores(2).cpuidle().build().unwrap();
+        fake.write("cpu1/cpuidle/state3/usage", "4294967296")
+            .unwrap();
+        fake.write("cpu1/cpuidle/state3/time", "1500000").unwrap();
+
+        let mut c = Core::discover(fake.path("cpu1")).unwrap();
+        let states = c.idle_states().unwrap();
+        let names = states.iter().map(|s| s.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["POLL", "C1", "C1E", "C6"]);
+        let c6 = &states[3];
+        assert_eq!((c6.latency(), c6.residency()), (133, 400));
+        assert_eq!(c6.usage(), 1 << 32);
+        assert_eq!(c6.time(), Duration::from_millis(1500));
+        assert!(!c6.disabled());
+        assert!(c6.is("c6") && c6.is("3") && c6.is("state3"));
+
+        c.set_idle_state_disabled("C6", true).unwrap();
+        assert_eq!(fake.read("cpu1/cpuidle/state3/disable").unwrap(), "1");
+        assert_eq!(fake.read("cpu0/cpuidle/state3/disable").unwrap(), "0");
+        assert!(matches!(
+            c.set_idle_state_disabled("C10", true),
+            Err(Error::InvalidInput { .. })
+        ));
+
+        let mut idle = discover_cpuidle_at(fake.root()).unwrap().unwrap();
+        assert_eq!(idle.driver(), Some("intel_idle"));
+        assert_eq!(idle.governor(), "menu");
+        idle.set_governor("teo").unwrap();
+        assert_eq!(fake.read("cpuidle/current_governor").unwrap(), "teo");
+        assert!(matches!(
+            idle.set_governor("haltpoll"),
+            Err(Error::UnknownGovernor { .. })
+        ));
+
+        let fake = FakeSysfs::builder().build().unwrap();
+        assert!(discover_cpuidle_at(fake.root()).unwrap().is_none());
+        let mut c = Core::discover(fake.path("cpu1")).unwrap();
+        assert!(c.idle_states().unwrap().is_empty());
+        assert!(matches!(
+            c.set_idle_state_disabled("C6", true),
+            Err(Error::NotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn broken_state() {
+        let fake = FakeSysfs::builder().cores(2).cpuidle().build().unwrap();
+        // reading fails with an I/O error rather than a missing attribute
+        fake.remove("cpu1/cpuidle/state2/usage").unwrap();
+        fs::create_dir(fake.path("cpu1/cpuidle/state2/usage")).unwrap();
+
+        let cores = discover_core_settings_at(fake.root()).unwrap();
+        assert_eq!(cores.len(), 2);
+        assert!(matches!(cores[1].idle_states(), Err(Error::Io { .. })));
+        assert_eq!(cores[0].idle_states().unwrap().len(), 4);
+    }
+}
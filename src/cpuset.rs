@@ -0,0 +1,218 @@
+use std::collections::btree_set;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+use error::Error;
+
+/// Upper bound for core numbers, the largest NR_CPUS the kernel can be built with
+const MAX_CPUS: u32 = 8192;
+
+/// A set of core numbers.
+///
+/// Parses from and displays in the kernel's cpulist format, e.g. "0-3,8-11".
+/// Parsing also accepts whitespace separated lists as used by related_cpus and affected_cpus.
+/// Core numbers of 8192 and above are refused, no kernel supports that many.
+/// Serializes as cpulist string.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct CpuSet(BTreeSet<u32>);
+
+impl CpuSet {
+    /// create an empty set
+    pub fn new() -> CpuSet {
+        CpuSet(BTreeSet::new())
+    }
+
+    /// add a core, returns whether it was not in the set before
+    pub fn insert(&mut self, cpu: u32) -> bool {
+        self.0.insert(cpu)
+    }
+
+    /// remove a core, returns whether it was in the set
+    pub fn remove(&mut self, cpu: u32) -> bool {
+        self.0.remove(&cpu)
+    }
+
+    /// returns whether the core is in the set
+    pub fn contains(&self, cpu: u32) -> bool {
+        self.0.contains(&cpu)
+    }
+
+    /// returns the number of cores in the set
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// returns whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// iterate over the cores in ascending order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.0.iter())
+    }
+
+    /// returns the cores in either set
+    pub fn union(&self, other: &CpuSet) -> CpuSet {
+        CpuSet(self.0.union(&other.0).cloned().collect())
+    }
+
+    /// returns the cores in both sets
+    pub fn intersection(&self, other: &CpuSet) -> CpuSet {
+        CpuSet(self.0.intersection(&other.0).cloned().collect())
+    }
+
+    /// returns the cores in this set but not in the other
+    pub fn difference(&self, other: &CpuSet) -> CpuSet {
+        CpuSet(self.0.difference(&other.0).cloned().collect())
+    }
+
+    /// returns whether all cores of this set are in the other
+    pub fn is_subset(&self, other: &CpuSet) -> bool {
+        self.0.is_subset(&other.0)
+    }
+
+    /// returns whether the sets have no core in common
+    pub fn is_disjoint(&self, other: &CpuSet) -> bool {
+        self.0.is_disjoint(&other.0)
+    }
+}
+
+/// Iterator over the cores of a [CpuSet]
+#[derive(Clone, Debug)]
+pub struct Iter<'a>(btree_set::Iter<'a, u32>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        self.0.next().cloned()
+    }
+}
+
+impl<'a> IntoIterator for &'a CpuSet {
+    type Item = u32;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl FromIterator<u32> for CpuSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> CpuSet {
+        CpuSet(iter.into_iter().collect())
+    }
+}
+
+impl fmt::Display for CpuSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        for c in self.iter() {
+            match ranges.last_mut() {
+                Some(r) if r.1 + 1 == c => r.1 = c,
+                _ => ranges.push((c, c)),
+            }
+        }
+
+        for (i, &(first, last)) in ranges.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            if first == last {
+                write!(f, "{}", first)?;
+            } else {
+                write!(f, "{}-{}", first, last)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CpuSet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<CpuSet, Error> {
+        let invalid = || Error::InvalidInput {
+            input: s.into(),
+            expected: format!("a list of cores below {} like 0-3,8", MAX_CPUS),
+        };
+
+        let mut set = CpuSet::new();
+        for part in s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|p| !p.is_empty())
+        {
+            let mut range = part.splitn(2, '-');
+            let first: u32 = range.next().unwrap_or("").parse().map_err(|_| invalid())?;
+            let last: u32 = match range.next() {
+                Some(l) => l.parse().map_err(|_| invalid())?,
+                None => first,
+            };
+            if last < first || last >= MAX_CPUS {
+                return Err(invalid());
+            }
+            set.0.extend(first..=last);
+        }
+
+        Ok(set)
+    }
+}
+
+impl From<CpuSet> for String {
+    fn from(set: CpuSet) -> String {
+        set.to_string()
+    }
+}
+
+impl TryFrom<String> for CpuSet {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<CpuSet, Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CpuSet;
+
+    #[test]
+    fn cpulist() {
+        let set: CpuSet = "0-3,8-11,13".parse().unwrap();
+        assert_eq!(set.len(), 9);
+        assert!(set.contains(9) && !set.contains(12));
+        assert_eq!(set.to_string(), "0-3,8-11,13");
+
+        // related_cpus style, unordered and overlapping input
+        let set: CpuSet = "3 1 2\n".parse().unwrap();
+        assert_eq!(set.to_string(), "1-3");
+        let set: CpuSet = "5,0-2,1".parse().unwrap();
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 1, 2, 5]);
+
+        assert!("".parse::<CpuSet>().unwrap().is_empty());
+        assert!("3-1".parse::<CpuSet>().is_err());
+        assert!("a-b".parse::<CpuSet>().is_err());
+        assert!("1-2-3".parse::<CpuSet>().is_err());
+        // would take ages and gigabytes to build
+        assert!("0-4294967295".parse::<CpuSet>().is_err());
+        assert!("8192".parse::<CpuSet>().is_err());
+        assert_eq!("8191".parse::<CpuSet>().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn set_ops() {
+        let a: CpuSet = "0-3".parse().unwrap();
+        let b: CpuSet = "2-5".parse().unwrap();
+        assert_eq!(a.union(&b).to_string(), "0-5");
+        assert_eq!(a.intersection(&b).to_string(), "2-3");
+        assert_eq!(a.difference(&b).to_string(), "0-1");
+        assert!(!a.is_subset(&b));
+        assert!(a.intersection(&b).is_subset(&a));
+        assert!(a.difference(&b).is_disjoint(&b));
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+
+/// The cpufreq scaling driver of a core as read from cpufreq/scaling_driver
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Driver {
+    /// intel_pstate in active mode, governs by itself (or via HWP)
+    IntelPstate,
+    /// intel_pstate in passive mode, works with the generic governors
+    IntelCpufreq,
+    /// amd-pstate in passive or guided mode
+    AmdPstate,
+    /// amd-pstate in active mode, governs via EPP
+    AmdPstateEpp,
+    /// ACPI P-states
+    AcpiCpufreq,
+    /// generic driver for device tree based (mostly ARM) systems
+    CpufreqDt,
+    /// any other driver
+    Other(String),
+}
+
+impl Driver {
+    /// returns the name as used by the kernel
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Driver::IntelPstate => "intel_pstate",
+            Driver::IntelCpufreq => "intel_cpufreq",
+            Driver::AmdPstate => "amd-pstate",
+            Driver::AmdPstateEpp => "amd-pstate-epp",
+            Driver::AcpiCpufreq => "acpi-cpufreq",
+            Driver::CpufreqDt => "cpufreq-dt",
+            Driver::Other(ref name) => name,
+        }
+    }
+
+    /// Whether the driver operates on discrete frequency steps (published in scaling_available_frequencies)
+    /// rather than a continuous range
+    pub fn has_discrete_steps(&self) -> bool {
+        matches!(*self, Driver::AcpiCpufreq | Driver::CpufreqDt)
+    }
+
+    /// Whether the driver may offer an energy performance preference (EPP).
+    /// Also depends on hardware support, e.g. HWP for intel_pstate in either mode.
+    pub fn supports_epp(&self) -> bool {
+        matches!(
+            *self,
+            Driver::IntelPstate | Driver::IntelCpufreq | Driver::AmdPstateEpp
+        )
+    }
+
+    /// Whether the driver accepts raw EPP values (0-255) besides the named preferences.
+    /// amd-pstate-epp only takes the names.
+    pub fn accepts_raw_epp(&self) -> bool {
+        matches!(*self, Driver::IntelPstate | Driver::IntelCpufreq)
+    }
+
+    /// Whether the driver may allow switching turbo/boost frequencies on and off
+    pub fn supports_boost(&self) -> bool {
+        matches!(
+            *self,
+            Driver::IntelPstate
+                | Driver::IntelCpufreq
+                | Driver::AmdPstate
+                | Driver::AmdPstateEpp
+                | Driver::AcpiCpufreq
+        )
+    }
+}
+
+impl<'a> From<&'a str> for Driver {
+    fn from(name: &'a str) -> Driver {
+        match name {
+            "intel_pstate" => Driver::IntelPstate,
+            "intel_cpufreq" => Driver::IntelCpufreq,
+            "amd-pstate" => Driver::AmdPstate,
+            "amd-pstate-epp" => Driver::AmdPstateEpp,
+            "acpi-cpufreq" => Driver::AcpiCpufreq,
+            "cpufreq-dt" => Driver::CpufreqDt,
+            other => Driver::Other(other.into()),
+        }
+    }
+}
+
+impl From<String> for Driver {
+    fn from(name: String) -> Driver {
+        Driver::from(name.as_str())
+    }
+}
+
+impl From<Driver> for String {
+    fn from(driver: Driver) -> String {
+        driver.as_str().into()
+    }
+}
+
+impl fmt::Display for Driver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Driver;
+
+    #[test]
+    fn names() {
+        for name in &[
+            "intel_pstate",
+            "intel_cpufreq",
+            "amd-pstate",
+            "amd-pstate-epp",
+            "acpi-cpufreq",
+            "cpufreq-dt",
+            "qcom-cpufreq-hw",
+        ] {
+            assert_eq!(Driver::from(*name).as_str(), *name);
+        }
+        assert_eq!(
+            Driver::from("qcom-cpufreq-hw"),
+            Driver::Other("qcom-cpufreq-hw".into())
+        );
+        assert!(Driver::AcpiCpufreq.has_discrete_steps());
+        assert!(Driver::IntelCpufreq.accepts_raw_epp());
+        assert!(!Driver::AmdPstate.supports_epp());
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::str::FromStr;
+
+use error::Error;
+
+/// Highest energy performance bias, favouring energy efficiency most
+const MAX: u32 = 15;
+
+/// Names understood by the kernel and x86_energy_perf_policy with their values
+const NAMES: &[(&str, u32)] = &[
+    ("performance", 0),
+    ("balance-performance", 4),
+    ("normal", 6),
+    ("balance-power", 8),
+    ("power", 15),
+];
+
+/// Intel energy performance bias (EPB) as read from cpu<x>/power/energy_perf_bias,
+/// ranging from 0 (performance) to 15 (power saving).
+///
+/// Parses from a number or one of the names performance (0), balance-performance (4),
+/// normal (6), balance-power (8) and power (15).
+/// Displays as name where there is one, otherwise as number.
+/// Serializes as plain number to match the sysfs representation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EnergyPerfBias(u32);
+
+impl EnergyPerfBias {
+    /// create an energy performance bias from a value in 0..=15
+    pub fn from_raw(raw: u32) -> Result<EnergyPerfBias, Error> {
+        if raw > MAX {
+            Err(Error::OutOfRange {
+                value: raw,
+                min: 0,
+                max: MAX,
+            })
+        } else {
+            Ok(EnergyPerfBias(raw))
+        }
+    }
+
+    /// returns the value in 0..=15
+    pub fn as_raw(self) -> u32 {
+        self.0
+    }
+
+    /// returns the name of the value if it has one
+    pub fn name(self) -> Option<&'static str> {
+        NAMES.iter().find(|n| n.1 == self.0).map(|n| n.0)
+    }
+}
+
+impl fmt::Display for EnergyPerfBias {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl FromStr for EnergyPerfBias {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<EnergyPerfBias, Error> {
+        let s = s.trim();
+        if let Ok(raw) = s.parse() {
+            return EnergyPerfBias::from_raw(raw);
+        }
+
+        NAMES
+            .iter()
+            .find(|n| n.0.eq_ignore_ascii_case(s))
+            .map(|n| EnergyPerfBias(n.1))
+            .ok_or_else(|| Error::InvalidInput {
+                input: s.into(),
+                expected: "0-15, performance, balance-performance, normal, balance-power or power"
+                    .into(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::EnergyPerfBias;
+    use fake::FakeSysfs;
+    use {Core, Error};
+
+    #[test]
+    fn parse() {
+        assert_eq!("normal".parse::<EnergyPerfBias>().unwrap().as_raw(), 6);
+        assert_eq!("Power".parse::<EnergyPerfBias>().unwrap().as_raw(), 15);
+        assert_eq!("7".parse::<EnergyPerfBias>().unwrap().as_raw(), 7);
+        assert!("16".parse::<EnergyPerfBias>().is_err());
+        assert!("balanced".parse::<EnergyPerfBias>().is_err());
+
+        assert_eq!(
+            EnergyPerfBias::from_raw(4).unwrap().to_string(),
+            "balance-performance"
+        );
+        assert_eq!(EnergyPerfBias::from_raw(7).unwrap().to_string(), "7");
+    }
+
+    #[test]
+    fn epb() {
+        let fake = FakeSysfs::builder().epb(6).build().unwrap();
+        let mut c = Core::discover(fake.path("cpu1")).unwrap();
+        assert_eq!(c.epb().map(|e| e.as_raw()), Some(6));
+
+        c.set_epb("balance-power".parse().unwrap()).unwrap();
+        assert_eq!(fake.read("cpu1/power/energy_perf_bias").unwrap(), "8");
+        assert_eq!(fake.read("cpu0/power/energy_perf_bias").unwrap(), "6");
+
+        let fake = FakeSysfs::builder().build().unwrap();
+        let mut c = Core::discover(fake.path("cpu1")).unwrap();
+        assert!(c.epb().is_none());
+        assert!(matches!(
+            c.set_epb(EnergyPerfBias::from_raw(0).unwrap()),
+            Err(Error::NotSupported { .. })
+        ));
+    }
+}
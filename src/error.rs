@@ -0,0 +1,124 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::result;
+
+use freq::Frequency;
+
+/// Result type used throughout mediocore
+pub type Result<T> = result::Result<T, Error>;
+
+/// Errors returned by mediocore
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The sysfs attribute does not exist, i.e. the kernel or driver does not support it
+    NotSupported { path: PathBuf },
+    /// Insufficient permissions to access the sysfs attribute (writing usually requires root)
+    PermissionDenied { path: PathBuf },
+    /// The content of the sysfs attribute could not be parsed
+    Parse { path: PathBuf, content: String },
+    /// The given value is not within min..=max
+    OutOfRange { value: u32, min: u32, max: u32 },
+    /// The given frequency is not within min..=max
+    FrequencyOutOfRange {
+        value: Frequency,
+        min: Frequency,
+        max: Frequency,
+    },
+    /// The governor is not in the list of available governors
+    UnknownGovernor {
+        governor: String,
+        available: Vec<String>,
+    },
+    /// The core is the last one online and cannot be taken offline
+    LastOnlineCpu { cpu: u32 },
+    /// User input could not be parsed
+    InvalidInput { input: String, expected: String },
+    /// The kernel refused to accept the value written to the attribute
+    Rejected { path: PathBuf, value: String },
+    /// Any other I/O error while accessing the attribute
+    Io { path: PathBuf, cause: io::Error },
+}
+
+impl Error {
+    /// Classify an io::Error which occurred while accessing the given path
+    pub(crate) fn from_io(path: &Path, cause: io::Error) -> Error {
+        let path = path.to_path_buf();
+        match cause.kind() {
+            io::ErrorKind::NotFound => Error::NotSupported { path },
+            io::ErrorKind::PermissionDenied => Error::PermissionDenied { path },
+            _ => Error::Io { path, cause },
+        }
+    }
+
+    /// Classify an io::Error which occurred while writing value to the given path
+    pub(crate) fn from_write(path: &Path, value: &str, cause: io::Error) -> Error {
+        match cause.kind() {
+            io::ErrorKind::InvalidInput => Error::Rejected {
+                path: path.to_path_buf(),
+                value: value.into(),
+            },
+            _ => Error::from_io(path, cause),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::NotSupported { ref path } => write!(f, "{} does not exist", path.display()),
+            Error::PermissionDenied { ref path } => {
+                write!(f, "Permission denied on {}", path.display())
+            }
+            Error::Parse {
+                ref path,
+                ref content,
+            } => write!(
+                f,
+                "Failed to parse {:?} read from {}",
+                content,
+                path.display()
+            ),
+            Error::OutOfRange { value, min, max } => {
+                write!(f, "Value {} not in {}..={}", value, min, max)
+            }
+            Error::FrequencyOutOfRange { value, min, max } => {
+                write!(f, "Frequency {} not in {}..={}", value, min, max)
+            }
+            Error::UnknownGovernor {
+                ref governor,
+                ref available,
+            } => write!(
+                f,
+                "Governor {} not available. Must be one of {:?}",
+                governor, available
+            ),
+            Error::LastOnlineCpu { cpu } => {
+                write!(f, "cpu{} is the last online core", cpu)
+            }
+            Error::InvalidInput {
+                ref input,
+                ref expected,
+            } => write!(f, "Invalid input {:?}, expected {}", input, expected),
+            Error::Rejected {
+                ref path,
+                ref value,
+            } => write!(f, "Kernel rejected {:?} for {}", value, path.display()),
+            Error::Io {
+                ref path,
+                ref cause,
+            } => write!(f, "Failed to access {}: {}", path.display(), cause),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io { ref cause, .. } => Some(cause),
+            _ => None,
+        }
+    }
+}
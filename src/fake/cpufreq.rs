@@ -0,0 +1,381 @@
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+use super::{parse_input, read_num, Builder, FakeSysfs, EINVAL, MARKER, PER_POLICY, UNSUPPORTED};
+use tunables;
+use FreqStats;
+
+/// Named energy performance preferences
+const EPPS: &[&str] = &[
+    "default",
+    "performance",
+    "balance_performance",
+    "balance_power",
+    "power",
+];
+
+/// Governors with tunables and their defaults
+pub(super) const TUNABLES: &[(&str, &[(&str, u32)])] = &[
+    (
+        "ondemand",
+        &[
+            ("up_threshold", 80),
+            ("sampling_rate", 10_000),
+            ("sampling_down_factor", 1),
+            ("io_is_busy", 0),
+            ("ignore_nice_load", 0),
+            ("powersave_bias", 0),
+        ],
+    ),
+    (
+        "conservative",
+        &[
+            ("up_threshold", 80),
+            ("down_threshold", 20),
+            ("freq_step", 5),
+            ("sampling_rate", 10_000),
+            ("sampling_down_factor", 1),
+            ("ignore_nice_load", 0),
+        ],
+    ),
+    ("schedutil", &[("rate_limit_us", 1000)]),
+];
+
+impl Builder {
+    /// Publish the boost control and the policies with their statistics and governor tunables
+    pub(super) fn build_policies(&self, fake: &FakeSysfs, online: &[u32]) -> io::Result<()> {
+        let per_policy_boost = self.driver.starts_with("amd-pstate");
+        match self.boost {
+            Some(enabled) if self.driver.starts_with("intel_") => {
+                fake.write("intel_pstate/no_turbo", if enabled { "0" } else { "1" })?
+            }
+            Some(enabled) if !per_policy_boost => {
+                fake.write("cpufreq/boost", if enabled { "1" } else { "0" })?
+            }
+            _ => (),
+        }
+
+        // one policy per core unless grouped
+        let mut policies = self.policies.clone();
+        for n in 0..self.cores {
+            if !policies.iter().any(|p| p.contains(&n)) {
+                policies.push(vec![n]);
+            }
+        }
+
+        for cpus in policies.iter().filter(|p| !p.is_empty()) {
+            let first = cpus[0];
+            let (min, max) = self
+                .core_cpuinfo
+                .get(&first)
+                .cloned()
+                .unwrap_or(self.cpuinfo);
+            let g = PathBuf::from(format!("cpufreq/policy{}", first));
+            let cpu_list = |cpus: &mut dyn Iterator<Item = &u32>| {
+                cpus.map(|c| c.to_string()).collect::<Vec<_>>().join(" ")
+            };
+            fake.write(
+                g.join("affected_cpus"),
+                &cpu_list(&mut cpus.iter().filter(|c| online.contains(c))),
+            )?;
+            fake.write(g.join("related_cpus"), &cpu_list(&mut cpus.iter()))?;
+            fake.write(g.join("cpuinfo_min_freq"), &min.to_string())?;
+            fake.write(g.join("cpuinfo_max_freq"), &max.to_string())?;
+            fake.write(g.join("scaling_min_freq"), &min.to_string())?;
+            fake.write(g.join("scaling_max_freq"), &max.to_string())?;
+            fake.write(g.join("scaling_cur_freq"), &min.to_string())?;
+            fake.write(g.join("cpuinfo_cur_freq"), &min.to_string())?;
+            fake.write(g.join("scaling_governor"), &self.governor)?;
+            fake.write(
+                g.join("scaling_setspeed"),
+                &if self.governor == "userspace" {
+                    min.to_string()
+                } else {
+                    UNSUPPORTED.to_string()
+                },
+            )?;
+            fake.write(g.join("scaling_driver"), &self.driver)?;
+            if let Some(ref epp) = self.epp {
+                fake.write(g.join("energy_performance_preference"), epp)?;
+                fake.write(
+                    g.join("energy_performance_available_preferences"),
+                    &EPPS.join(" "),
+                )?;
+            }
+            if self.amd_pstate.is_some() {
+                let ranking = self.prefcore_rankings.get(&first).cloned().unwrap_or(166);
+                fake.write(g.join("amd_pstate_highest_perf"), &ranking.to_string())?;
+                fake.write(g.join("amd_pstate_prefcore_ranking"), &ranking.to_string())?;
+                fake.write(g.join("amd_pstate_max_freq"), &max.to_string())?;
+                fake.write(
+                    g.join("amd_pstate_lowest_nonlinear_freq"),
+                    &((min + max) / 2).to_string(),
+                )?;
+            }
+            match self.boost {
+                Some(enabled) if per_policy_boost => {
+                    fake.write(g.join("boost"), if enabled { "1" } else { "0" })?
+                }
+                _ => (),
+            }
+            fake.write(
+                g.join("scaling_available_governors"),
+                &self.governors.join(" "),
+            )?;
+            if !self.frequencies.is_empty() {
+                // the kernel lists them in descending order with a trailing space
+                let mut freqs = self.frequencies.clone();
+                freqs.sort_by(|a, b| b.cmp(a));
+                let freqs = freqs.iter().fold(String::new(), |mut s, f| {
+                    s.push_str(&format!("{} ", f));
+                    s
+                });
+                fake.write(g.join("scaling_available_frequencies"), &freqs)?;
+            }
+
+            if self.stats {
+                let mut freqs = if self.frequencies.is_empty() {
+                    vec![min, max]
+                } else {
+                    self.frequencies.clone()
+                };
+                freqs.sort_by(|a, b| b.cmp(a));
+                freqs.dedup();
+                write_stats(&fake.path(g.join("stats")), &freqs, &[], &[])?;
+                fake.write(g.join("stats/reset"), "")?;
+            }
+
+            // like the kernel, link each online core's cpufreq directory to its policy
+            for c in cpus.iter().filter(|c| online.contains(c)) {
+                let core = fake.path(format!("cpu{}", c));
+                symlink(format!("../cpufreq/policy{}", first), core.join("cpufreq"))?;
+            }
+        }
+        sync_tunables(&fake.root)?;
+        Ok(())
+    }
+}
+
+/// Publish the tunables of the governors in use and remove those of governors no longer in use,
+/// either globally or per policy
+pub(super) fn sync_tunables(root: &Path) -> io::Result<()> {
+    let per_policy = fs::read_to_string(root.join(MARKER))?.trim() == PER_POLICY;
+    let cpufreq = root.join("cpufreq");
+    let mut in_use = Vec::new();
+
+    for policy in fs::read_dir(&cpufreq)? {
+        let policy = policy?.path();
+        let current = match fs::read_to_string(policy.join("scaling_governor")) {
+            Ok(gov) => gov.trim().to_string(),
+            Err(_) => continue,
+        };
+        if per_policy {
+            for &(gov, defaults) in TUNABLES {
+                sync_tunables_dir(&policy.join(gov), gov == current, defaults)?;
+            }
+        }
+        in_use.push(current);
+    }
+    if !per_policy {
+        for &(gov, defaults) in TUNABLES {
+            sync_tunables_dir(
+                &cpufreq.join(gov),
+                in_use.iter().any(|g| g == gov),
+                defaults,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn sync_tunables_dir(dir: &Path, in_use: bool, defaults: &[(&str, u32)]) -> io::Result<()> {
+    if in_use && !dir.is_dir() {
+        fs::create_dir_all(dir)?;
+        for (name, value) in defaults {
+            fs::write(dir.join(name), format!("{}\n", value))?;
+        }
+    } else if !in_use && dir.is_dir() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// Emulate the attributes of a cpufreq policy, including their effect on other attributes
+pub(super) fn store_policy(root: &Path, dir: &Path, attr: &str, value: &str) -> io::Result<()> {
+    let stored = match attr {
+        "scaling_min_freq" | "scaling_max_freq" => {
+            let freq = parse_input(value)?;
+            let lo = read_num(&dir.join("cpuinfo_min_freq"))?;
+            let hi = read_num(&dir.join("cpuinfo_max_freq"))?;
+            let freq = freq.max(lo).min(hi);
+            let rejected = if attr == "scaling_min_freq" {
+                freq > read_num(&dir.join("scaling_max_freq"))?
+            } else {
+                freq < read_num(&dir.join("scaling_min_freq"))?
+            };
+            if rejected {
+                return Err(io::Error::from_raw_os_error(EINVAL));
+            }
+            freq.to_string()
+        }
+        "scaling_governor" => {
+            let gov = value.trim();
+            let available = fs::read_to_string(dir.join("scaling_available_governors"))?;
+            if !available.split_whitespace().any(|g| g == gov) {
+                return Err(io::Error::from_raw_os_error(EINVAL));
+            }
+            gov.to_string()
+        }
+        "scaling_setspeed" => {
+            let gov = fs::read_to_string(dir.join("scaling_governor"))?;
+            if gov.trim() != "userspace" {
+                return Err(io::Error::from_raw_os_error(EINVAL));
+            }
+            // the userspace governor clamps to the scaling limits and applies the frequency right away
+            let lo = read_num(&dir.join("scaling_min_freq"))?;
+            let hi = read_num(&dir.join("scaling_max_freq"))?;
+            let freq = parse_input(value)?.max(lo).min(hi);
+            let cur = read_num(&dir.join("scaling_cur_freq"))?;
+            if cur != freq {
+                record_transition(dir, cur, freq)?;
+            }
+            for cur in &["scaling_cur_freq", "cpuinfo_cur_freq"] {
+                fs::write(dir.join(cur), format!("{}\n", freq))?;
+            }
+            freq.to_string()
+        }
+        _ => {
+            let epp = value.trim();
+            let available =
+                fs::read_to_string(dir.join("energy_performance_available_preferences"))?;
+            let driver = fs::read_to_string(dir.join("scaling_driver"))?;
+            let named = available.split_whitespace().any(|e| e == epp);
+            let raw = epp.parse::<u32>().map(|e| e <= 255).unwrap_or(false)
+                && (driver.trim() == "intel_pstate" || driver.trim() == "intel_cpufreq");
+            if !named && !raw {
+                return Err(io::Error::from_raw_os_error(EINVAL));
+            }
+            epp.to_string()
+        }
+    };
+    fs::write(dir.join(attr), format!("{}\n", stored))?;
+
+    if attr == "scaling_governor" {
+        sync_tunables(root)?;
+        // the userspace governor starts out at the current frequency
+        let speed = if stored == "userspace" {
+            fs::read_to_string(dir.join("scaling_cur_freq"))?
+        } else {
+            UNSUPPORTED.to_string()
+        };
+        fs::write(
+            dir.join("scaling_setspeed"),
+            format!("{}\n", speed.trim_end()),
+        )?;
+    }
+
+    // keep the current frequency within the new limits
+    if attr == "scaling_min_freq" || attr == "scaling_max_freq" {
+        let lo = read_num(&dir.join("scaling_min_freq"))?;
+        let hi = read_num(&dir.join("scaling_max_freq"))?;
+        for cur in &["scaling_cur_freq", "cpuinfo_cur_freq", "scaling_setspeed"] {
+            let cur = dir.join(cur);
+            if cur.is_file() && fs::read_to_string(&cur)?.trim() != UNSUPPORTED {
+                let freq = read_num(&cur)?.max(lo).min(hi);
+                fs::write(cur, format!("{}\n", freq))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Emulate the range checks of the governor tunables
+pub(super) fn store_tunable(dir: &Path, attr: &str, value: &str) -> io::Result<String> {
+    let governor = dir.file_name().and_then(|g| g.to_str()).unwrap_or("");
+    let tunable = parse_input(value)?;
+    match tunables::range(governor, attr) {
+        Some((min, max)) if tunable < min || tunable > max => {
+            Err(io::Error::from_raw_os_error(EINVAL))
+        }
+        _ => Ok(tunable.to_string()),
+    }
+}
+
+/// Write time_in_state, total_trans and trans_table in the kernel's format.
+/// Missing times and counts are zero.
+fn write_stats(dir: &Path, freqs: &[u32], times: &[u64], counts: &[Vec<u64>]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let count = |from: usize, to: usize| counts.get(from).and_then(|r| r.get(to)).cloned();
+
+    let mut time_in_state = String::new();
+    let mut total = 0;
+    let mut table = "   From  :    To\n         : ".to_string();
+    for f in freqs {
+        table.push_str(&format!("{:>9} ", f));
+    }
+    table.push('\n');
+    for (i, f) in freqs.iter().enumerate() {
+        time_in_state.push_str(&format!("{} {}\n", f, times.get(i).cloned().unwrap_or(0)));
+        table.push_str(&format!("{:>9}: ", f));
+        for j in 0..freqs.len() {
+            let c = count(i, j).unwrap_or(0);
+            total += c;
+            table.push_str(&format!("{:>9} ", c));
+        }
+        table.push('\n');
+    }
+
+    fs::write(dir.join("time_in_state"), time_in_state)?;
+    fs::write(dir.join("total_trans"), format!("{}\n", total))?;
+    fs::write(dir.join("trans_table"), table)
+}
+
+/// Count a transition in the statistics of a policy, if it has them
+fn record_transition(policy: &Path, from: u32, to: u32) -> io::Result<()> {
+    let dir = policy.join("stats");
+    let stats = match FreqStats::discover(dir.clone()) {
+        Ok(stats) => stats,
+        Err(_) => return Ok(()),
+    };
+    let table = match stats.trans_table() {
+        Some(table) => table,
+        None => return Ok(()),
+    };
+    let freqs = table.freqs();
+    let counts = freqs
+        .iter()
+        .map(|f| {
+            freqs
+                .iter()
+                .map(|t| {
+                    let c = table.transitions(*f, *t).unwrap_or(0);
+                    if f.as_khz() == from && t.as_khz() == to {
+                        c + 1
+                    } else {
+                        c
+                    }
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>();
+    let times = stats
+        .time_in_state()
+        .iter()
+        // back to clock ticks of 10ms
+        .map(|r| r.time().as_millis() as u64 / 10)
+        .collect::<Vec<_>>();
+    let freqs = freqs.iter().map(|f| f.as_khz()).collect::<Vec<_>>();
+    write_stats(&dir, &freqs, &times, &counts)
+}
+
+/// Emulate clearing the statistics of a policy
+pub(super) fn store_stats_reset(dir: &Path) -> io::Result<()> {
+    let freqs = fs::read_to_string(dir.join("time_in_state"))?
+        .lines()
+        .filter_map(|l| l.split_whitespace().next().and_then(|f| f.parse().ok()))
+        .collect::<Vec<u32>>();
+    write_stats(dir, &freqs, &[], &[])
+}
@@ -0,0 +1,55 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::{parse_switch, Builder, FakeSysfs, EINVAL};
+
+/// Idle states of intel_idle: name, description, exit latency and target residency in microseconds
+const IDLE_STATES: &[(&str, &str, u32, u32)] = &[
+    ("POLL", "CPUIDLE CORE POLL IDLE", 0, 0),
+    ("C1", "MWAIT 0x00", 2, 2),
+    ("C1E", "MWAIT 0x01", 10, 20),
+    ("C6", "MWAIT 0x20", 133, 400),
+];
+
+impl Builder {
+    /// Publish the driver and governors of cpuidle
+    pub(super) fn build_cpuidle(&self, fake: &FakeSysfs) -> io::Result<()> {
+        if self.cpuidle {
+            fake.write("cpuidle/current_driver", "intel_idle")?;
+            fake.write("cpuidle/current_governor", "menu")?;
+            fake.write("cpuidle/available_governors", "ladder menu teo")?;
+        }
+        Ok(())
+    }
+
+    /// Publish the idle states of a core
+    pub(super) fn build_idle_states(&self, fake: &FakeSysfs, core: &Path) -> io::Result<()> {
+        if self.cpuidle {
+            for (k, &(name, desc, latency, residency)) in IDLE_STATES.iter().enumerate() {
+                let state = core.join(format!("cpuidle/state{}", k));
+                fake.write(state.join("name"), name)?;
+                fake.write(state.join("desc"), desc)?;
+                fake.write(state.join("latency"), &latency.to_string())?;
+                fake.write(state.join("residency"), &residency.to_string())?;
+                fake.write(state.join("usage"), "0")?;
+                fake.write(state.join("time"), "0")?;
+                fake.write(state.join("disable"), "0")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Emulate disabling idle states and switching the cpuidle governor
+pub(super) fn store_cpuidle(dir: &Path, attr: &str, value: &str) -> io::Result<String> {
+    if attr == "disable" {
+        return parse_switch(value);
+    }
+    let gov = value.trim();
+    let available = fs::read_to_string(dir.join("available_governors"))?;
+    if !available.split_whitespace().any(|g| g == gov) {
+        return Err(io::Error::from_raw_os_error(EINVAL));
+    }
+    Ok(gov.to_string())
+}
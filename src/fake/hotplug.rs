@@ -0,0 +1,89 @@
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+use super::{cpulist, parse_cpulist, EBUSY, EINVAL};
+
+/// Emulate hot-plugging a core by writing its online file
+pub(super) fn store_online(root: &Path, dir: &Path, value: &str) -> io::Result<()> {
+    let cpu: u32 = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.trim_start_matches("cpu").parse().ok())
+        .ok_or_else(|| io::Error::from_raw_os_error(EINVAL))?;
+    let online = match value.trim() {
+        "0" => false,
+        "1" => true,
+        _ => return Err(io::Error::from_raw_os_error(EINVAL)),
+    };
+
+    let list = root.join("online");
+    let mut cpus = match fs::read_to_string(&list) {
+        Ok(cpus) => parse_cpulist(&cpus)?,
+        // kernels without the online list still know the state of each core
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => online_cores(root)?,
+        Err(e) => return Err(e),
+    };
+    if !online && cpus == [cpu] {
+        return Err(io::Error::from_raw_os_error(EBUSY));
+    }
+    cpus.retain(|c| *c != cpu);
+    if online {
+        cpus.push(cpu);
+        cpus.sort();
+    }
+    if list.exists() {
+        let offline = parse_cpulist(&fs::read_to_string(root.join("possible"))?)?
+            .into_iter()
+            .filter(|c| !cpus.contains(c))
+            .collect::<Vec<_>>();
+        fs::write(&list, format!("{}\n", cpulist(&cpus)))?;
+        fs::write(root.join("offline"), format!("{}\n", cpulist(&offline)))?;
+    }
+
+    // the kernel removes the cpufreq link of offline cores and updates the policy
+    let link = dir.join("cpufreq");
+    for policy in fs::read_dir(root.join("cpufreq"))? {
+        let policy = policy?.path();
+        let related = fs::read_to_string(policy.join("related_cpus"))?;
+        let related = related
+            .split_whitespace()
+            .filter_map(|c| c.parse::<u32>().ok())
+            .collect::<Vec<_>>();
+        if !related.contains(&cpu) {
+            continue;
+        }
+        let affected = related
+            .iter()
+            .filter(|c| cpus.contains(c))
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        fs::write(
+            policy.join("affected_cpus"),
+            format!("{}\n", affected.join(" ")),
+        )?;
+
+        if online && fs::symlink_metadata(&link).is_err() {
+            let name = policy.file_name().unwrap_or_default().to_string_lossy();
+            symlink(format!("../cpufreq/{}", name), &link)?;
+        }
+    }
+    if !online && fs::symlink_metadata(&link).is_ok() {
+        fs::remove_file(&link)?;
+    }
+
+    fs::write(dir.join("online"), if online { "1\n" } else { "0\n" })
+}
+
+/// The possible cores which are not marked offline in their online file
+fn online_cores(root: &Path) -> io::Result<Vec<u32>> {
+    Ok(parse_cpulist(&fs::read_to_string(root.join("possible"))?)?
+        .into_iter()
+        .filter(|c| {
+            fs::read_to_string(root.join(format!("cpu{}/online", c)))
+                .map(|v| v.trim() != "0")
+                .unwrap_or(true)
+        })
+        .collect())
+}
@@ -0,0 +1,630 @@
+//! A fake cpufreq sysfs tree for testing code built on mediocore without root or cpufreq hardware.
+//!
+//! [FakeSysfs] creates a temporary directory laid out like /sys with the cpu tree in devices/system/cpu.
+//! Pass [FakeSysfs::root] to [discover_core_settings_at](::discover_core_settings_at)
+//! or to `mdcr --sysfs-root`.
+//!
+//! Once test code installs the [Emulation] as [Backend](::Backend) with `set_backend(fake::emulation())`,
+//! writes done by mediocore to a fake tree are checked the way the kernel would check them:
+//! frequencies are clamped to the cpuinfo limits, a max below the current min (or a min above the current max)
+//! is rejected with EINVAL and so are unknown governors and boost values other than 0 and 1.
+//! Switching the intel_pstate or amd_pstate status swaps drivers and governors.
+//! Applying ondemand, conservative or schedutil publishes their tunables, which are range checked on writes.
+//! cpufreq statistics count the transitions done through scaling_setspeed and are cleared by writing stats/reset.
+//! Idle states can be disabled and the cpuidle governor switched among the available ones.
+//! scaling_setspeed only accepts writes while the userspace governor is applied and sets the current frequency.
+//! Read-only attributes refuse writes with EACCES.
+//! Hot-plugging cores through cpuN/online updates the online lists, policies and cpufreq links.
+//! The tree is removed when the [FakeSysfs] is dropped.
+
+mod cpufreq;
+mod cpuidle;
+mod hotplug;
+mod pstate;
+mod thermal;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use self::cpufreq::{store_policy, store_stats_reset, store_tunable, TUNABLES};
+use self::cpuidle::store_cpuidle;
+use self::hotplug::store_online;
+use self::pstate::store_pstate;
+#[cfg(test)]
+use set_backend;
+use {Backend, CpuSet, EnergyPerfBias, Sysfs};
+
+/// Name of the file marking the root of a fake tree
+const MARKER: &str = ".mediocore-fake";
+/// Content of scaling_setspeed unless the userspace governor is applied
+const UNSUPPORTED: &str = "<unsupported>";
+/// Content of the marker if governor tunables are per policy
+const PER_POLICY: &str = "governor_per_policy";
+
+/// errno returned by the kernel for rejected values
+const EINVAL: i32 = 22;
+/// errno returned by the kernel when writing read-only attributes
+const EACCES: i32 = 13;
+/// errno returned when writing attributes which do not exist
+const ENOENT: i32 = 2;
+/// errno returned by the kernel when offlining the last online core
+const EBUSY: i32 = 16;
+
+/// Attributes the kernel does not allow writing to
+const READ_ONLY: &[&str] = &[
+    "possible",
+    "present",
+    "offline",
+    "physical_package_id",
+    "die_id",
+    "cluster_id",
+    "core_id",
+    "thread_siblings_list",
+    "affected_cpus",
+    "related_cpus",
+    "cpuinfo_min_freq",
+    "cpuinfo_max_freq",
+    "cpuinfo_cur_freq",
+    "scaling_cur_freq",
+    "scaling_available_governors",
+    "scaling_available_frequencies",
+    "scaling_driver",
+    "cpu_capacity",
+    "prefcore",
+    "amd_pstate_highest_perf",
+    "amd_pstate_lowest_nonlinear_freq",
+    "amd_pstate_max_freq",
+    "amd_pstate_prefcore_ranking",
+    "energy_performance_available_preferences",
+    "time_in_state",
+    "total_trans",
+    "trans_table",
+    "name",
+    "desc",
+    "latency",
+    "residency",
+    "usage",
+    "time",
+    "current_driver",
+    "available_governors",
+    "type",
+    "temp",
+    "max_state",
+];
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary cpufreq tree, removed on drop
+#[derive(Debug)]
+pub struct FakeSysfs {
+    /// The temporary directory standing in for /sys
+    base: PathBuf,
+    /// base/devices/system/cpu
+    root: PathBuf,
+}
+
+/// Describes the tree to be built by [FakeSysfs::builder]
+#[derive(Clone, Debug)]
+pub struct Builder {
+    cores: u32,
+    cpuinfo: (u32, u32),
+    core_cpuinfo: HashMap<u32, (u32, u32)>,
+    capacities: HashMap<u32, u32>,
+    boost: Option<bool>,
+    intel_pstate: Option<String>,
+    amd_pstate: Option<String>,
+    prefcore_rankings: HashMap<u32, u32>,
+    epp: Option<String>,
+    epb: Option<u32>,
+    stats: bool,
+    cpuidle: bool,
+    thermal_zones: Vec<(String, i32)>,
+    cooling_devices: Vec<(String, u32, u32)>,
+    governors: Vec<String>,
+    governor: String,
+    governor_per_policy: bool,
+    frequencies: Vec<u32>,
+    driver: String,
+    policies: Vec<Vec<u32>>,
+    offline: Vec<u32>,
+    packages: u32,
+    threads_per_core: u32,
+    efficiency: Vec<u32>,
+}
+
+impl FakeSysfs {
+    /// Start describing a fake tree.
+    /// Defaults to 4 cores driven by intel_pstate with limits of 800MHz and 3GHz,
+    /// the performance and powersave governors and powersave applied.
+    pub fn builder() -> Builder {
+        Builder {
+            cores: 4,
+            cpuinfo: (800_000, 3_000_000),
+            core_cpuinfo: HashMap::new(),
+            capacities: HashMap::new(),
+            boost: None,
+            intel_pstate: None,
+            amd_pstate: None,
+            prefcore_rankings: HashMap::new(),
+            epp: None,
+            epb: None,
+            stats: false,
+            cpuidle: false,
+            thermal_zones: vec![],
+            cooling_devices: vec![],
+            governors: vec!["performance".into(), "powersave".into()],
+            governor: "powersave".into(),
+            governor_per_policy: false,
+            frequencies: vec![],
+            driver: "intel_pstate".into(),
+            policies: vec![],
+            offline: vec![],
+            packages: 1,
+            threads_per_core: 1,
+            efficiency: vec![],
+        }
+    }
+
+    /// returns the directory to be used in place of /sys/devices/system/cpu.
+    /// Attributes outside of it, like /sys/devices/cpu_core, are found relative to it, e.g. "../../cpu_core/cpus".
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// returns the directory to be used in place of /sys/class/thermal
+    pub fn thermal_root(&self) -> PathBuf {
+        self.base.join("class/thermal")
+    }
+
+    /// returns the path of an attribute relative to the root, e.g. "cpu0/cpufreq/scaling_max_freq".
+    /// As on a real system cpuN/cpufreq links to cpufreq/policyN.
+    pub fn path<P: AsRef<Path>>(&self, attr: P) -> PathBuf {
+        self.root.join(attr)
+    }
+
+    /// Read an attribute relative to the root with trailing whitespace removed
+    pub fn read<P: AsRef<Path>>(&self, attr: P) -> io::Result<String> {
+        fs::read_to_string(self.path(attr)).map(|s| s.trim_end().to_string())
+    }
+
+    /// Write an attribute relative to the root, bypassing the kernel emulation.
+    /// Creates missing parent directories, so it can also be used to extend the tree.
+    pub fn write<P: AsRef<Path>>(&self, attr: P, value: &str) -> io::Result<()> {
+        let p = self.path(attr);
+        if let Some(parent) = p.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(p, format!("{}\n", value))
+    }
+
+    /// Remove an attribute or directory relative to the root
+    pub fn remove<P: AsRef<Path>>(&self, attr: P) -> io::Result<()> {
+        let p = self.path(attr);
+        if p.is_dir() {
+            fs::remove_dir_all(p)
+        } else {
+            fs::remove_file(p)
+        }
+    }
+}
+
+impl Drop for FakeSysfs {
+    fn drop(&mut self) {
+        EMULATION.detach(&self.root);
+        if let Err(e) = fs::remove_dir_all(&self.base) {
+            warn!("Failed to remove fake sysfs tree {:?}: {}", self.base, e);
+        }
+    }
+}
+
+impl Builder {
+    /// Number of cores (cpu0..cpu<n-1>)
+    pub fn cores(mut self, n: u32) -> Builder {
+        self.cores = n;
+        self
+    }
+
+    /// cpuinfo_min_freq and cpuinfo_max_freq in kHz for all cores
+    pub fn cpuinfo(mut self, min: u32, max: u32) -> Builder {
+        self.cpuinfo = (min, max);
+        self
+    }
+
+    /// cpuinfo_min_freq and cpuinfo_max_freq in kHz for a single core
+    pub fn core_cpuinfo(mut self, core: u32, min: u32, max: u32) -> Builder {
+        self.core_cpuinfo.insert(core, (min, max));
+        self
+    }
+
+    /// cpu_capacity of a single core as on heterogeneous ARM CPUs, the biggest cores have 1024.
+    /// Not published unless given.
+    pub fn core_capacity(mut self, core: u32, capacity: u32) -> Builder {
+        self.capacities.insert(core, capacity);
+        self
+    }
+
+    /// List of available governors; the first one is applied unless [Builder::governor] is given
+    pub fn governors(mut self, govs: &[&str]) -> Builder {
+        self.governors = govs.iter().map(|g| g.to_string()).collect();
+        self.governor = self.governors.first().cloned().unwrap_or_default();
+        self
+    }
+
+    /// Currently applied governor
+    pub fn governor(mut self, gov: &str) -> Builder {
+        self.governor = gov.into();
+        self
+    }
+
+    /// Publish governor tunables per policy (cpufreq/policyN/<governor>) as drivers setting
+    /// governor_per_policy do, instead of globally in cpufreq/<governor>
+    pub fn governor_per_policy(mut self, per_policy: bool) -> Builder {
+        self.governor_per_policy = per_policy;
+        self
+    }
+
+    /// Discrete frequency steps in kHz published as scaling_available_frequencies.
+    /// Not published unless given.
+    pub fn frequencies(mut self, freqs: &[u32]) -> Builder {
+        self.frequencies = freqs.to_vec();
+        self
+    }
+
+    /// Name of the scaling driver
+    pub fn driver(mut self, driver: &str) -> Builder {
+        self.driver = driver.into();
+        self
+    }
+
+    /// Publish a boost control in the place the driver uses: intel_pstate/no_turbo for intel_pstate
+    /// and intel_cpufreq, a boost file per policy for amd-pstate and cpufreq/boost for all others.
+    /// Not published unless given.
+    pub fn boost(mut self, enabled: bool) -> Builder {
+        self.boost = Some(enabled);
+        self
+    }
+
+    /// Publish the global intel_pstate settings with the given status (active, passive or off),
+    /// a min_perf_pct of 25, a max_perf_pct of 100 and hwp_dynamic_boost disabled.
+    /// Also applies the matching driver and governors (intel_pstate with performance and powersave
+    /// when active, intel_cpufreq with the generic governors when passive).
+    pub fn intel_pstate(mut self, status: &str) -> Builder {
+        self.apply_pstate_mode("intel_pstate", status);
+        self.intel_pstate = Some(status.into());
+        self
+    }
+
+    /// Publish the global amd-pstate settings with the given status (active, passive, guided or disable)
+    /// and prefcore enabled, as well as the per-core amd_pstate_* attributes.
+    /// Also applies the matching driver and governors (amd-pstate-epp with performance and powersave
+    /// when active, amd-pstate with the generic governors otherwise).
+    pub fn amd_pstate(mut self, status: &str) -> Builder {
+        self.apply_pstate_mode("amd_pstate", status);
+        self.amd_pstate = Some(status.into());
+        self
+    }
+
+    /// amd_pstate_prefcore_ranking and amd_pstate_highest_perf of a single core, 166 unless given
+    pub fn core_prefcore_ranking(mut self, core: u32, ranking: u32) -> Builder {
+        self.prefcore_rankings.insert(core, ranking);
+        self
+    }
+
+    /// Publish an energy performance preference with the given value and the usual named
+    /// preferences: default, performance, balance_performance, balance_power and power.
+    /// Like the kernel, raw values are accepted as well if the driver is intel_pstate.
+    pub fn epp(mut self, epp: &str) -> Builder {
+        self.epp = Some(epp.into());
+        self
+    }
+
+    /// Publish power/energy_perf_bias with the given value for all cores
+    pub fn epb(mut self, epb: u32) -> Builder {
+        self.epb = Some(epb);
+        self
+    }
+
+    /// Publish cpufreq statistics for each policy, starting out at zero. They cover the
+    /// [Builder::frequencies] or, without those, the cpuinfo limits.
+    pub fn stats(mut self) -> Builder {
+        self.stats = true;
+        self
+    }
+
+    /// Publish the idle states of intel_idle (POLL, C1, C1E and C6) for all cores
+    /// and the menu governor with teo and ladder as alternatives
+    pub fn cpuidle(mut self) -> Builder {
+        self.cpuidle = true;
+        self
+    }
+
+    /// Add a thermal zone in [FakeSysfs::thermal_root] with the given type and temperature in millidegrees Celsius.
+    /// It trips passive cooling at 95°C and shuts down at 105°C.
+    pub fn thermal_zone(mut self, kind: &str, temp: i32) -> Builder {
+        self.thermal_zones.push((kind.into(), temp));
+        self
+    }
+
+    /// Add a cooling device in [FakeSysfs::thermal_root] with the given type and current and highest state
+    pub fn cooling_device(mut self, kind: &str, cur_state: u32, max_state: u32) -> Builder {
+        self.cooling_devices
+            .push((kind.into(), cur_state, max_state));
+        self
+    }
+
+    /// Group cores into a shared policy, numbered after its first core.
+    /// Cores not grouped get a policy of their own.
+    pub fn policy(mut self, cores: &[u32]) -> Builder {
+        self.policies.push(cores.to_vec());
+        self
+    }
+
+    /// Take the given cores offline. Like the kernel, their cpufreq links are removed.
+    pub fn offline(mut self, cores: &[u32]) -> Builder {
+        self.offline = cores.to_vec();
+        self
+    }
+
+    /// Number of packages (and NUMA nodes), the cores are split evenly among them
+    pub fn packages(mut self, n: u32) -> Builder {
+        self.packages = n.max(1);
+        self
+    }
+
+    /// Number of SMT threads per physical core; siblings are numbered consecutively
+    pub fn threads_per_core(mut self, n: u32) -> Builder {
+        self.threads_per_core = n.max(1);
+        self
+    }
+
+    /// Make a hybrid CPU with the given cores as efficiency cores (E-cores) and the others as
+    /// performance cores (P-cores), as listed in /sys/devices/cpu_atom/cpus and /sys/devices/cpu_core/cpus
+    pub fn efficiency(mut self, cores: &[u32]) -> Builder {
+        self.efficiency = cores.to_vec();
+        self
+    }
+
+    /// Create the tree in a fresh temporary directory
+    pub fn build(self) -> io::Result<FakeSysfs> {
+        let base = ::std::env::temp_dir().join(format!(
+            "mediocore-fake-{}-{}",
+            process::id(),
+            NEXT_ID.fetch_add(1, Ordering::SeqCst)
+        ));
+        let root = base.join("devices/system/cpu");
+        fs::create_dir_all(&root)?;
+        let fake = FakeSysfs { base, root };
+        fake.write(
+            MARKER,
+            if self.governor_per_policy {
+                PER_POLICY
+            } else {
+                ""
+            },
+        )?;
+        // present on real systems and must not be mistaken for cores
+        fs::create_dir_all(fake.path("cpufreq"))?;
+        fs::create_dir_all(fake.path("cpuidle"))?;
+        self.build_cpuidle(&fake)?;
+
+        let all = (0..self.cores).collect::<Vec<_>>();
+        let online = all
+            .iter()
+            .filter(|c| !self.offline.contains(c))
+            .cloned()
+            .collect::<Vec<_>>();
+        fake.write("possible", &cpulist(&all))?;
+        fake.write("present", &cpulist(&all))?;
+        fake.write("online", &cpulist(&online))?;
+        fake.write("offline", &cpulist(&self.offline))?;
+        self.build_pstate(&fake)?;
+        self.build_thermal(&fake)?;
+        if !self.efficiency.is_empty() {
+            let performance = all
+                .iter()
+                .filter(|c| !self.efficiency.contains(c))
+                .cloned()
+                .collect::<Vec<_>>();
+            fake.write("../../cpu_core/cpus", &cpulist(&performance))?;
+            fake.write("../../cpu_atom/cpus", &cpulist(&self.efficiency))?;
+        }
+        for n in all.iter() {
+            // like on x86, cpu0 cannot be hot-plugged
+            let core = PathBuf::from(format!("cpu{}", n));
+            fs::create_dir_all(fake.path(&core))?;
+            if *n != 0 {
+                let state = if online.contains(n) { "1" } else { "0" };
+                fake.write(core.join("online"), state)?;
+            }
+            if let Some(epb) = self.epb {
+                fake.write(core.join("power/energy_perf_bias"), &epb.to_string())?;
+            }
+            self.build_idle_states(&fake, &core)?;
+            if let Some(capacity) = self.capacities.get(n) {
+                fake.write(core.join("cpu_capacity"), &capacity.to_string())?;
+            }
+
+            let per_package = (self.cores / self.packages).max(1);
+            let package = n / per_package;
+            let first_sibling = n - n % self.threads_per_core;
+            let siblings = (first_sibling..first_sibling + self.threads_per_core)
+                .filter(|s| *s < self.cores)
+                .collect::<Vec<_>>();
+            let t = core.join("topology");
+            fake.write(t.join("physical_package_id"), &package.to_string())?;
+            fake.write(t.join("die_id"), "0")?;
+            fake.write(
+                t.join("core_id"),
+                &((n % per_package) / self.threads_per_core).to_string(),
+            )?;
+            fake.write(
+                t.join("cluster_id"),
+                &(first_sibling / self.threads_per_core).to_string(),
+            )?;
+            fake.write(t.join("thread_siblings_list"), &cpulist(&siblings))?;
+            fs::create_dir_all(fake.path(format!("node{}", package)))?;
+            symlink(
+                format!("../node{}", package),
+                fake.path(core.join(format!("node{}", package))),
+            )?;
+        }
+        self.build_policies(&fake, &online)?;
+
+        EMULATION.attach(&fake.root)?;
+        debug!("Built fake sysfs tree {:?}", fake.root);
+
+        Ok(fake)
+    }
+}
+
+/// Format cores in the kernel's cpulist format, e.g. "0-3,8"
+fn cpulist(cpus: &[u32]) -> String {
+    cpus.iter().cloned().collect::<CpuSet>().to_string()
+}
+
+/// Backend emulating the kernel for writes within the attached fake trees.
+/// Writes to any other path go to the file system as with [Sysfs].
+/// Test code installs it with [set_backend](::set_backend), see [emulation].
+#[derive(Debug)]
+pub struct Emulation {
+    /// Roots of the fake trees in use
+    roots: Mutex<Vec<PathBuf>>,
+}
+
+static EMULATION: Emulation = Emulation {
+    roots: Mutex::new(Vec::new()),
+};
+
+/// returns the kernel emulation, to be installed by test code:
+/// `mediocore::set_backend(mediocore::fake::emulation())`.
+/// Trees built with [FakeSysfs::builder] are attached to it already.
+pub fn emulation() -> &'static Emulation {
+    &EMULATION
+}
+
+/// Install the [Emulation] for the unit tests, which share one process
+#[cfg(test)]
+pub(crate) fn install_emulation() {
+    // fails if another test installed it first, the unit tests use no other backend
+    let _ = set_backend(&EMULATION);
+}
+
+impl Emulation {
+    /// Emulate the kernel for writes to the fake tree at root, e.g. one built by
+    /// another process and passed to `mdcr --sysfs-root`.
+    /// Fails if root is not the root of a fake tree.
+    pub fn attach<P: AsRef<Path>>(&self, root: P) -> io::Result<()> {
+        let root = root.as_ref();
+        if !root.join(MARKER).is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a fake sysfs tree", root.display()),
+            ));
+        }
+
+        let mut roots = self.roots();
+        if !roots.iter().any(|r| r == root) {
+            roots.push(root.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn detach(&self, root: &Path) {
+        self.roots().retain(|r| r != root);
+    }
+
+    fn roots(&self) -> MutexGuard<'_, Vec<PathBuf>> {
+        // a test panicking while holding the lock leaves the list intact
+        self.roots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Backend for Emulation {
+    fn write(&self, path: &Path, value: &str) -> io::Result<()> {
+        let root = self.roots().iter().find(|r| path.starts_with(r)).cloned();
+        match root {
+            Some(root) => {
+                debug!("Emulating kernel write of {:?} to {:?}", value, path);
+                store(&root, path, value)
+            }
+            None => Sysfs.write(path, value),
+        }
+    }
+}
+
+fn read_num(path: &Path) -> io::Result<u32> {
+    fs::read_to_string(path)?
+        .trim()
+        .parse()
+        .map_err(|_| io::Error::from_raw_os_error(EINVAL))
+}
+
+fn parse_input(value: &str) -> io::Result<u32> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| io::Error::from_raw_os_error(EINVAL))
+}
+
+/// Parse the kernel's cpulist format, e.g. "0-3,8"
+fn parse_cpulist(list: &str) -> io::Result<Vec<u32>> {
+    list.parse::<CpuSet>()
+        .map(|cpus| cpus.iter().collect())
+        .map_err(|_| io::Error::from_raw_os_error(EINVAL))
+}
+
+/// Emulate the store callbacks of the kernel attributes
+fn store(root: &Path, path: &Path, value: &str) -> io::Result<()> {
+    let attr = path.file_name().and_then(|a| a.to_str()).unwrap_or("");
+    let dir = path.parent().unwrap_or(path);
+
+    // sysfs does not allow creating attributes
+    if !path.is_file() {
+        return Err(io::Error::from_raw_os_error(ENOENT));
+    }
+    if READ_ONLY.contains(&attr) || (attr == "online" && dir == root) {
+        return Err(io::Error::from_raw_os_error(EACCES));
+    }
+
+    let stored = match attr {
+        "online" => return store_online(root, dir, value),
+        "reset" if dir.ends_with("stats") => return store_stats_reset(dir),
+        "scaling_min_freq"
+        | "scaling_max_freq"
+        | "scaling_governor"
+        | "scaling_setspeed"
+        | "energy_performance_preference" => return store_policy(root, dir, attr, value),
+        "disable" | "current_governor" => store_cpuidle(dir, attr, value)?,
+        "status" | "min_perf_pct" | "max_perf_pct"
+            if dir.ends_with("intel_pstate") || dir.ends_with("amd_pstate") =>
+        {
+            store_pstate(root, dir, attr, value)?
+        }
+        "energy_perf_bias" => {
+            // like the kernel, accept the names as well but store the number
+            let epb = value
+                .parse::<EnergyPerfBias>()
+                .map_err(|_| io::Error::from_raw_os_error(EINVAL))?;
+            epb.as_raw().to_string()
+        }
+        _ if TUNABLES.iter().any(|t| dir.ends_with(t.0)) => store_tunable(dir, attr, value)?,
+        "no_turbo" | "boost" | "hwp_dynamic_boost" => parse_switch(value)?,
+        _ => value.trim_end().to_string(),
+    };
+
+    fs::write(path, format!("{}\n", stored))
+}
+
+/// Accept 0 or 1 like the kernel's boolean attributes
+fn parse_switch(value: &str) -> io::Result<String> {
+    match value.trim() {
+        "0" | "1" => Ok(value.trim().to_string()),
+        _ => Err(io::Error::from_raw_os_error(EINVAL)),
+    }
+}
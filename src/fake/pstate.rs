@@ -0,0 +1,119 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::{parse_input, read_num, Builder, FakeSysfs, EINVAL};
+
+/// Governors offered by drivers working with the generic governors
+const GENERIC_GOVERNORS: &[&str] = &[
+    "conservative",
+    "ondemand",
+    "userspace",
+    "powersave",
+    "performance",
+    "schedutil",
+];
+
+impl Builder {
+    /// Apply the scaling driver and governors the pstate driver uses in the given mode
+    pub(super) fn apply_pstate_mode(&mut self, pstate: &str, status: &str) {
+        if let Ok(Some((driver, governors))) = pstate_mode(pstate, status) {
+            self.driver = driver.into();
+            self.governors = governors.iter().map(|g| g.to_string()).collect();
+            self.governor = fallback_governor(governors).into();
+        }
+    }
+
+    /// Publish the global intel_pstate and amd_pstate settings
+    pub(super) fn build_pstate(&self, fake: &FakeSysfs) -> io::Result<()> {
+        if let Some(ref status) = self.intel_pstate {
+            fake.write("intel_pstate/status", status)?;
+            fake.write("intel_pstate/min_perf_pct", "25")?;
+            fake.write("intel_pstate/max_perf_pct", "100")?;
+            fake.write("intel_pstate/hwp_dynamic_boost", "0")?;
+        }
+        if let Some(ref status) = self.amd_pstate {
+            fake.write("amd_pstate/status", status)?;
+            fake.write("amd_pstate/prefcore", "enabled")?;
+        }
+        Ok(())
+    }
+}
+
+/// Scaling driver and governors offered by intel_pstate or amd_pstate in the given mode.
+/// None for the modes in which the driver is not in use, EINVAL for modes the driver does not know.
+fn pstate_mode(
+    pstate: &str,
+    status: &str,
+) -> io::Result<Option<(&'static str, &'static [&'static str])>> {
+    let active: &[&str] = &["performance", "powersave"];
+    match (pstate, status) {
+        ("intel_pstate", "active") => Ok(Some(("intel_pstate", active))),
+        ("intel_pstate", "passive") => Ok(Some(("intel_cpufreq", GENERIC_GOVERNORS))),
+        ("intel_pstate", "off") => Ok(None),
+        ("amd_pstate", "active") => Ok(Some(("amd-pstate-epp", active))),
+        ("amd_pstate", "disable") => Ok(None),
+        ("amd_pstate", "passive") | ("amd_pstate", "guided") => {
+            Ok(Some(("amd-pstate", GENERIC_GOVERNORS)))
+        }
+        _ => Err(io::Error::from_raw_os_error(EINVAL)),
+    }
+}
+
+/// Governor applied after switching modes if the current one is not available
+fn fallback_governor(governors: &[&str]) -> &'static str {
+    if governors.contains(&"schedutil") {
+        "schedutil"
+    } else {
+        "powersave"
+    }
+}
+
+/// Emulate the global intel_pstate and amd_pstate attributes
+pub(super) fn store_pstate(root: &Path, dir: &Path, attr: &str, value: &str) -> io::Result<String> {
+    if attr == "status" {
+        let pstate = dir.file_name().and_then(|p| p.to_str()).unwrap_or("");
+        return store_pstate_status(root, pstate, value);
+    }
+    let pct = parse_input(value)?;
+    if pct > 100 {
+        return Err(io::Error::from_raw_os_error(EINVAL));
+    }
+    // the kernel keeps min <= max by clamping
+    let pct = if attr == "min_perf_pct" {
+        pct.min(read_num(&dir.join("max_perf_pct"))?)
+    } else {
+        pct.max(read_num(&dir.join("min_perf_pct"))?)
+    };
+    Ok(pct.to_string())
+}
+
+/// Emulate switching the intel_pstate or amd_pstate mode, which swaps the scaling driver
+/// and governors of all policies
+fn store_pstate_status(root: &Path, pstate: &str, value: &str) -> io::Result<String> {
+    let status = value.trim();
+    let (driver, governors) = match pstate_mode(pstate, status)? {
+        Some(mode) => mode,
+        None => return Ok(status.into()),
+    };
+
+    for policy in fs::read_dir(root.join("cpufreq"))? {
+        let policy = policy?.path();
+        if !policy.join("scaling_driver").is_file() {
+            continue;
+        }
+        fs::write(policy.join("scaling_driver"), format!("{}\n", driver))?;
+        fs::write(
+            policy.join("scaling_available_governors"),
+            format!("{}\n", governors.join(" ")),
+        )?;
+        let current = fs::read_to_string(policy.join("scaling_governor"))?;
+        if !governors.contains(&current.trim()) {
+            fs::write(
+                policy.join("scaling_governor"),
+                format!("{}\n", fallback_governor(governors)),
+            )?;
+        }
+    }
+    Ok(status.into())
+}
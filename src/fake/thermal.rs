@@ -0,0 +1,33 @@
+use std::io;
+
+use super::{Builder, FakeSysfs};
+
+impl Builder {
+    /// Publish the thermal zones and cooling devices
+    pub(super) fn build_thermal(&self, fake: &FakeSysfs) -> io::Result<()> {
+        let thermal = fake.thermal_root();
+        for (n, (kind, temp)) in self.thermal_zones.iter().enumerate() {
+            let zone = thermal.join(format!("thermal_zone{}", n));
+            fake.write(zone.join("type"), kind)?;
+            fake.write(zone.join("temp"), &temp.to_string())?;
+            for (k, (trip, temp)) in [("passive", 95_000), ("critical", 105_000)]
+                .iter()
+                .enumerate()
+            {
+                fake.write(zone.join(format!("trip_point_{}_type", k)), trip)?;
+                fake.write(
+                    zone.join(format!("trip_point_{}_temp", k)),
+                    &temp.to_string(),
+                )?;
+                fake.write(zone.join(format!("trip_point_{}_hyst", k)), "0")?;
+            }
+        }
+        for (n, (kind, cur, max)) in self.cooling_devices.iter().enumerate() {
+            let dev = thermal.join(format!("cooling_device{}", n));
+            fake.write(dev.join("type"), kind)?;
+            fake.write(dev.join("cur_state"), &cur.to_string())?;
+            fake.write(dev.join("max_state"), &max.to_string())?;
+        }
+        Ok(())
+    }
+}
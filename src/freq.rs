@@ -0,0 +1,238 @@
+use std::fmt;
+use std::str::FromStr;
+
+use error::Error;
+
+const KHZ_PER_MHZ: u32 = 1_000;
+const KHZ_PER_GHZ: u32 = 1_000_000;
+
+/// A cpu frequency, stored in kHz like sysfs does.
+///
+/// Parses from strings with an explicit unit such as "800MHz", "2.4GHz" or "1200000kHz" (case-insensitive).
+/// Numbers without a unit are refused, since they are ambiguous.
+/// Displays in the largest unit that fits, e.g. "2.4GHz" or "800MHz".
+/// Serializes as plain kHz to match the sysfs representation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Frequency(u32);
+
+impl Frequency {
+    /// create a frequency from a value in kHz
+    pub fn from_khz(khz: u32) -> Frequency {
+        Frequency(khz)
+    }
+
+    /// create a frequency from a value in MHz.
+    /// Fails with [Error::InvalidInput] if it does not fit in kHz.
+    pub fn from_mhz(mhz: u32) -> Result<Frequency, Error> {
+        mhz.checked_mul(KHZ_PER_MHZ)
+            .map(Frequency)
+            .ok_or_else(|| Error::InvalidInput {
+                input: format!("{}MHz", mhz),
+                expected: format!("a frequency of at most {}", Frequency(u32::MAX)),
+            })
+    }
+
+    /// returns the frequency in kHz
+    pub fn as_khz(self) -> u32 {
+        self.0
+    }
+
+    /// returns the frequency in MHz
+    pub fn as_mhz(self) -> f64 {
+        f64::from(self.0) / f64::from(KHZ_PER_MHZ)
+    }
+
+    /// returns the frequency in GHz
+    pub fn as_ghz(self) -> f64 {
+        f64::from(self.0) / f64::from(KHZ_PER_GHZ)
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // divisor, number of fractional digits, unit
+        let (div, width, unit) = if self.0 >= KHZ_PER_GHZ {
+            (KHZ_PER_GHZ, 6, "GHz")
+        } else if self.0 >= KHZ_PER_MHZ {
+            (KHZ_PER_MHZ, 3, "MHz")
+        } else {
+            (1, 0, "kHz")
+        };
+
+        write!(f, "{}", self.0 / div)?;
+        let frac = self.0 % div;
+        if frac != 0 {
+            // pad to the number of fractional digits, then drop trailing zeros
+            let digits = format!("{:0width$}", frac, width = width);
+            write!(f, ".{}", digits.trim_end_matches('0'))?;
+        }
+        write!(f, "{}", unit)
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Frequency, Error> {
+        let invalid = || Error::InvalidInput {
+            input: s.into(),
+            expected: "a frequency with unit, e.g. 800MHz, 2.4GHz or 1200000kHz".into(),
+        };
+
+        let s = s.trim();
+        let split = s.find(|c: char| c.is_alphabetic()).ok_or_else(invalid)?;
+        let (num, unit) = s.split_at(split);
+
+        let mult = match unit.trim().to_lowercase().as_str() {
+            "khz" => 1,
+            "mhz" => KHZ_PER_MHZ,
+            "ghz" => KHZ_PER_GHZ,
+            _ => return Err(invalid()),
+        };
+
+        let mut parts = num.trim().splitn(2, '.');
+        let int = parts.next().unwrap_or("");
+        let frac = parts.next().unwrap_or("");
+        if int.is_empty() && frac.is_empty() {
+            return Err(invalid());
+        }
+
+        let int: u32 = if int.is_empty() {
+            0
+        } else {
+            int.parse().map_err(|_| invalid())?
+        };
+        let mut khz = int.checked_mul(mult).ok_or_else(invalid)?;
+
+        // add the fractional digits, which must not go below 1kHz
+        let mut scale = mult;
+        for d in frac.chars() {
+            let d = d.to_digit(10).ok_or_else(invalid)?;
+            scale /= 10;
+            if scale == 0 && d != 0 {
+                return Err(invalid());
+            }
+            khz = khz.checked_add(d * scale).ok_or_else(invalid)?;
+        }
+
+        Ok(Frequency(khz))
+    }
+}
+
+/// How to round a frequency to one of the discrete steps published in scaling_available_frequencies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Snap {
+    /// the closest step, the lower one on a tie
+    Nearest,
+    /// the lowest step at or above the frequency
+    Up,
+    /// the highest step at or below the frequency
+    Down,
+}
+
+impl Snap {
+    /// Round freq to one of the steps. Frequencies beyond the outermost steps snap to those.
+    /// Returns freq unchanged if there are no steps.
+    pub fn apply(self, freq: Frequency, steps: &[Frequency]) -> Frequency {
+        let above = steps.iter().filter(|s| **s >= freq).min();
+        let below = steps.iter().filter(|s| **s <= freq).max();
+
+        let snapped = match (self, above, below) {
+            (Snap::Up, Some(a), _) | (Snap::Down, _, Some(a)) => Some(a),
+            (Snap::Nearest, Some(a), Some(b)) => {
+                if a.0 - freq.0 < freq.0 - b.0 {
+                    Some(a)
+                } else {
+                    Some(b)
+                }
+            }
+            // nothing in the preferred direction, so take what is there
+            (_, a, b) => a.or(b),
+        };
+
+        snapped.cloned().unwrap_or(freq)
+    }
+}
+
+impl FromStr for Snap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Snap, Error> {
+        match s {
+            "nearest" => Ok(Snap::Nearest),
+            "up" => Ok(Snap::Up),
+            "down" => Ok(Snap::Down),
+            _ => Err(Error::InvalidInput {
+                input: s.into(),
+                expected: "one of nearest, up or down".into(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Frequency, Snap};
+
+    #[test]
+    fn parse() {
+        let f = |s: &str| s.parse::<Frequency>().map(|f| f.as_khz()).ok();
+
+        assert_eq!(f("800MHz"), Some(800_000));
+        assert_eq!(f("2.4GHz"), Some(2_400_000));
+        assert_eq!(f("1200000kHz"), Some(1_200_000));
+        assert_eq!(f(" 1.5 ghz "), Some(1_500_000));
+        assert_eq!(f("0.8GHz"), Some(800_000));
+        assert_eq!(f("800.5MHz"), Some(800_500));
+
+        // no unit, unknown unit, sub-kHz precision and garbage
+        assert_eq!(f("800"), None);
+        assert_eq!(f("800Mhzz"), None);
+        assert_eq!(f("800.5kHz"), None);
+        assert_eq!(f("GHz"), None);
+        assert_eq!(f("1.2.3GHz"), None);
+        assert_eq!(f("-1GHz"), None);
+        assert_eq!(f("5000GHz"), None);
+        assert_eq!(f("4294968MHz"), None);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Frequency::from_khz(2_400_000).to_string(), "2.4GHz");
+        assert_eq!(Frequency::from_khz(3_000_000).to_string(), "3GHz");
+        assert_eq!(Frequency::from_khz(2_400_001).to_string(), "2.400001GHz");
+        assert_eq!(Frequency::from_mhz(800).unwrap().to_string(), "800MHz");
+        assert!(Frequency::from_mhz(4_294_968).is_err());
+        assert_eq!(Frequency::from_khz(800_500).to_string(), "800.5MHz");
+        assert_eq!(Frequency::from_khz(500).to_string(), "500kHz");
+
+        for khz in &[1, 999, 1_000, 800_500, 2_400_001, 4_200_000] {
+            let f = Frequency::from_khz(*khz);
+            assert_eq!(f.to_string().parse::<Frequency>().unwrap(), f);
+        }
+    }
+
+    #[test]
+    fn snap() {
+        let steps = [800_000, 1_600_000, 2_400_000]
+            .iter()
+            .map(|f| Frequency::from_khz(*f))
+            .collect::<Vec<_>>();
+        let snap = |mode: Snap, f| mode.apply(Frequency::from_khz(f), &steps).as_khz();
+
+        assert_eq!(snap(Snap::Up, 1_000_000), 1_600_000);
+        assert_eq!(snap(Snap::Down, 1_000_000), 800_000);
+        assert_eq!(snap(Snap::Nearest, 1_000_000), 800_000);
+        assert_eq!(snap(Snap::Nearest, 1_300_000), 1_600_000);
+        assert_eq!(snap(Snap::Nearest, 1_200_000), 800_000);
+        assert_eq!(snap(Snap::Up, 1_600_000), 1_600_000);
+
+        // beyond the outermost steps
+        assert_eq!(snap(Snap::Up, 3_000_000), 2_400_000);
+        assert_eq!(snap(Snap::Down, 500_000), 800_000);
+
+        // no steps published
+        assert_eq!(Snap::Up.apply(Frequency::from_khz(1), &[]).as_khz(), 1);
+    }
+}
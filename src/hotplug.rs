@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+
+use {optional, read_cpulist, write_attr, Error, Result, SYSFS_CPU_ROOT};
+
+fn online_file(root: &Path, cpu: u32) -> PathBuf {
+    root.join(format!("cpu{}/online", cpu))
+}
+
+/// returns whether the core in /sys/devices/system/cpu can be taken offline and brought back online
+pub fn is_hotpluggable(cpu: u32) -> bool {
+    is_hotpluggable_at(SYSFS_CPU_ROOT, cpu)
+}
+
+/// returns whether the core in <root> can be taken offline and brought back online.
+/// Cores without an online file (like cpu0 on x86) cannot.
+pub fn is_hotpluggable_at<P: AsRef<Path>>(root: P, cpu: u32) -> bool {
+    online_file(root.as_ref(), cpu).is_file()
+}
+
+/// Take the core in /sys/devices/system/cpu offline or bring it back online
+pub fn set_online(cpu: u32, online: bool) -> Result<()> {
+    set_online_at(SYSFS_CPU_ROOT, cpu, online)
+}
+
+/// Take the core in <root> offline or bring it back online.
+/// Refuses to offline the last online core and cores which cannot be hot-plugged.
+/// Run discovery again afterwards to pick up the changed set of cores.
+pub fn set_online_at<P: AsRef<Path>>(root: P, cpu: u32, online: bool) -> Result<()> {
+    let root = root.as_ref();
+    let path = online_file(root, cpu);
+    if !path.is_file() {
+        return Err(Error::NotSupported { path });
+    }
+
+    // without the online list of the kernel, leave the check to the kernel
+    let all_online = optional(read_cpulist(&root.join("online")))?.unwrap_or_default();
+    if !online && all_online.len() == 1 && all_online.contains(cpu) {
+        return Err(Error::LastOnlineCpu { cpu });
+    }
+
+    debug!("Setting cpu{} online: {}", cpu, online);
+    write_attr(&path, if online { "1" } else { "0" })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_hotpluggable_at, set_online_at};
+    use fake::{install_emulation, FakeSysfs};
+    use {discover_at, discover_core_settings_at, Error};
+
+    #[test]
+    fn hotplug() {
+        install_emulation();
+        let fake = FakeSysfs::builder().cores(3).build().unwrap();
+        assert!(!is_hotpluggable_at(fake.root(), 0));
+        assert!(is_hotpluggable_at(fake.root(), 2));
+
+        let mut cores = discover_core_settings_at(fake.root()).unwrap();
+        cores[2].set_online(false).unwrap();
+        let d = discover_at(fake.root()).unwrap();
+        assert_eq!(d.cores().len(), 2);
+        assert_eq!(d.online().to_string(), "0-1");
+
+        match set_online_at(fake.root(), 0, false) {
+            Err(Error::NotSupported { .. }) => (),
+            r => panic!("Expected cpu0 not to be hot-pluggable, got {:?}", r),
+        }
+
+        set_online_at(fake.root(), 2, true).unwrap();
+        let d = discover_at(fake.root()).unwrap();
+        assert_eq!(d.cores().len(), 3);
+        assert_eq!(d.cores()[2].curr_gov(), "powersave");
+    }
+
+    #[test]
+    fn hotplug_last_online() {
+        let fake = FakeSysfs::builder().cores(2).offline(&[0]).build().unwrap();
+        match set_online_at(fake.root(), 1, false) {
+            Err(Error::LastOnlineCpu { cpu: 1 }) => (),
+            r => panic!("Expected refusal, got {:?}", r),
+        }
+    }
+}
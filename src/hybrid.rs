@@ -0,0 +1,212 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use {optional, read_cpulist, Error, Result};
+
+/// Kind of a core on hybrid CPUs (e.g. Intel Alder Lake and newer) which mix
+/// performance cores (P-cores) and efficiency cores (E-cores)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoreKind {
+    /// P-core, listed in /sys/devices/cpu_core/cpus
+    Performance,
+    /// E-core, listed in /sys/devices/cpu_atom/cpus
+    Efficiency,
+    /// Not a hybrid CPU or the kernel does not tell
+    Unknown,
+}
+
+impl CoreKind {
+    /// Look up the kind of the core in the cpu_core and cpu_atom pmu directories,
+    /// which are found two levels above the cpu root in /sys/devices.
+    pub(crate) fn discover(root: &Path, cpu: u32) -> Result<CoreKind> {
+        let devices = match root.parent().and_then(|p| p.parent()) {
+            Some(d) => d,
+            None => return Ok(CoreKind::Unknown),
+        };
+
+        let listed = |pmu: &str| -> Result<bool> {
+            Ok(optional(read_cpulist(&devices.join(pmu).join("cpus")))?
+                .is_some_and(|cpus| cpus.contains(cpu)))
+        };
+
+        if listed("cpu_core")? {
+            Ok(CoreKind::Performance)
+        } else if listed("cpu_atom")? {
+            Ok(CoreKind::Efficiency)
+        } else {
+            Ok(CoreKind::Unknown)
+        }
+    }
+
+    /// returns the name used for parsing and display
+    pub fn as_str(&self) -> &str {
+        match *self {
+            CoreKind::Performance => "performance",
+            CoreKind::Efficiency => "efficiency",
+            CoreKind::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for CoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CoreKind {
+    type Err = Error;
+
+    /// Parses "performance" (or "p") and "efficiency" (or "e"), ignoring case
+    fn from_str(s: &str) -> Result<CoreKind> {
+        match s.to_lowercase().as_str() {
+            "performance" | "p" => Ok(CoreKind::Performance),
+            "efficiency" | "e" => Ok(CoreKind::Efficiency),
+            "unknown" => Ok(CoreKind::Unknown),
+            _ => Err(Error::InvalidInput {
+                input: s.into(),
+                expected: "performance, efficiency or unknown".into(),
+            }),
+        }
+    }
+}
+
+/// Capacity tier of a core on heterogeneous ARM CPUs (big.LITTLE, DynamIQ),
+/// derived from cpu<x>/cpu_capacity relative to the other cores
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CapacityTier {
+    /// Cores with the lowest capacity
+    Little,
+    /// Cores between the lowest and the highest capacity, e.g. on CPUs with three clusters
+    Medium,
+    /// Cores with the highest capacity
+    Big,
+}
+
+impl CapacityTier {
+    /// Classify a capacity among the capacities of all cores.
+    /// Returns None if all cores have the same capacity.
+    pub(crate) fn classify(capacity: u32, all: &[u32]) -> Option<CapacityTier> {
+        let lowest = all.iter().min()?;
+        let highest = all.iter().max()?;
+        if lowest == highest {
+            None
+        } else if capacity == *highest {
+            Some(CapacityTier::Big)
+        } else if capacity == *lowest {
+            Some(CapacityTier::Little)
+        } else {
+            Some(CapacityTier::Medium)
+        }
+    }
+
+    /// returns the name used for parsing and display
+    pub fn as_str(&self) -> &str {
+        match *self {
+            CapacityTier::Little => "little",
+            CapacityTier::Medium => "medium",
+            CapacityTier::Big => "big",
+        }
+    }
+}
+
+impl fmt::Display for CapacityTier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CapacityTier {
+    type Err = Error;
+
+    /// Parses "little", "medium" (or "mid") and "big", ignoring case
+    fn from_str(s: &str) -> Result<CapacityTier> {
+        match s.to_lowercase().as_str() {
+            "little" => Ok(CapacityTier::Little),
+            "medium" | "mid" => Ok(CapacityTier::Medium),
+            "big" => Ok(CapacityTier::Big),
+            _ => Err(Error::InvalidInput {
+                input: s.into(),
+                expected: "little, medium or big".into(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CapacityTier, CoreKind};
+    use fake::FakeSysfs;
+    use {discover_core_settings_at, Core};
+
+    #[test]
+    fn tiers() {
+        let all = [1024, 1024, 870, 446, 446];
+        assert_eq!(CapacityTier::classify(1024, &all), Some(CapacityTier::Big));
+        assert_eq!(
+            CapacityTier::classify(870, &all),
+            Some(CapacityTier::Medium)
+        );
+        assert_eq!(
+            CapacityTier::classify(446, &all),
+            Some(CapacityTier::Little)
+        );
+        assert_eq!(CapacityTier::classify(1024, &[1024, 1024]), None);
+        assert_eq!("BIG".parse::<CapacityTier>().unwrap(), CapacityTier::Big);
+        assert!("prime".parse::<CapacityTier>().is_err());
+    }
+
+    #[test]
+    fn hybrid() {
+        let fake = FakeSysfs::builder()
+            .cores(4)
+            .efficiency(&[2, 3])
+            .core_cpuinfo(2, 800_000, 2_000_000)
+            .core_cpuinfo(3, 800_000, 2_000_000)
+            .build()
+            .unwrap();
+
+        let cores = discover_core_settings_at(fake.root()).unwrap();
+        let kinds = cores.iter().map(|c| c.kind()).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                CoreKind::Performance,
+                CoreKind::Performance,
+                CoreKind::Efficiency,
+                CoreKind::Efficiency
+            ]
+        );
+        assert_eq!("E".parse::<CoreKind>().unwrap(), CoreKind::Efficiency);
+        assert!("big".parse::<CoreKind>().is_err());
+
+        // not hybrid
+        fake.remove("../../cpu_core").unwrap();
+        fake.remove("../../cpu_atom").unwrap();
+        let c = Core::discover(fake.path("cpu0")).unwrap();
+        assert_eq!(c.kind(), CoreKind::Unknown);
+    }
+
+    #[test]
+    fn capacity_tiers() {
+        let fake = FakeSysfs::builder()
+            .cores(4)
+            .core_capacity(0, 446)
+            .core_capacity(1, 446)
+            .core_capacity(2, 1024)
+            .core_capacity(3, 1024)
+            .offline(&[3])
+            .build()
+            .unwrap();
+
+        let cores = discover_core_settings_at(fake.root()).unwrap();
+        assert_eq!(cores[0].capacity(), Some(446));
+        assert_eq!(cores[0].tier(), Some(CapacityTier::Little));
+        // offline cores still count
+        assert_eq!(cores[2].tier(), Some(CapacityTier::Big));
+        assert_eq!(Core::discover(fake.path("cpu2")).unwrap().tier(), None);
+    }
+}
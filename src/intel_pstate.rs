@@ -0,0 +1,209 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use {optional, read_attr, read_num, write_attr, Error, Result, SYSFS_CPU_ROOT};
+
+/// Operation mode of intel_pstate as read from intel_pstate/status
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntelPstateStatus {
+    /// intel_pstate governs by itself and offers the performance and powersave governors only.
+    /// The scaling driver is intel_pstate.
+    Active,
+    /// intel_pstate works with the generic governors, the scaling driver is intel_cpufreq
+    Passive,
+    /// intel_pstate is not in use
+    Off,
+}
+
+impl IntelPstateStatus {
+    /// returns the name as used by the kernel
+    pub fn as_str(&self) -> &str {
+        match *self {
+            IntelPstateStatus::Active => "active",
+            IntelPstateStatus::Passive => "passive",
+            IntelPstateStatus::Off => "off",
+        }
+    }
+}
+
+impl fmt::Display for IntelPstateStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for IntelPstateStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<IntelPstateStatus> {
+        match s.trim() {
+            "active" => Ok(IntelPstateStatus::Active),
+            "passive" => Ok(IntelPstateStatus::Passive),
+            "off" => Ok(IntelPstateStatus::Off),
+            _ => Err(Error::InvalidInput {
+                input: s.into(),
+                expected: "active, passive or off".into(),
+            }),
+        }
+    }
+}
+
+/// find the global intel_pstate settings in /sys/devices/system/cpu/intel_pstate
+pub fn discover_intel_pstate() -> Result<Option<IntelPstate>> {
+    discover_intel_pstate_at(SYSFS_CPU_ROOT)
+}
+
+/// find the global intel_pstate settings in <root>/intel_pstate.
+/// Returns None on systems without intel_pstate.
+pub fn discover_intel_pstate_at<P: AsRef<Path>>(root: P) -> Result<Option<IntelPstate>> {
+    let dir = root.as_ref().join("intel_pstate");
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    IntelPstate::discover(dir).map(Some)
+}
+
+/// Global settings of the intel_pstate driver. They apply to all cores on top of the
+/// per-core scaling limits, e.g. a max_perf_pct of 50 caps all cores at half their maximum
+/// performance no matter what scaling_max_freq says.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IntelPstate {
+    /// Path to the intel_pstate directory
+    dir: PathBuf,
+    /// Operation mode
+    status: IntelPstateStatus,
+    /// Lower limit of the P-state in percent of the maximum performance, not present while off
+    min_perf_pct: Option<u32>,
+    /// Upper limit of the P-state in percent of the maximum performance, not present while off
+    max_perf_pct: Option<u32>,
+    /// Whether HWP raises the minimum performance on IO wakeups, only present in active mode with HWP
+    hwp_dynamic_boost: Option<bool>,
+}
+
+impl IntelPstate {
+    /// discover the settings in the given intel_pstate directory
+    pub fn discover(dir: PathBuf) -> Result<IntelPstate> {
+        let status_file = dir.join("status");
+        let status = read_attr(&status_file)?.parse().map_err(|e| match e {
+            Error::InvalidInput { input, .. } => Error::Parse {
+                path: status_file,
+                content: input,
+            },
+            e => e,
+        })?;
+        let min_perf_pct = optional(read_num(&dir.join("min_perf_pct")))?;
+        let max_perf_pct = optional(read_num(&dir.join("max_perf_pct")))?;
+        let hwp_dynamic_boost = optional(read_num(&dir.join("hwp_dynamic_boost")))?.map(|b| b != 0);
+
+        Ok(IntelPstate {
+            dir,
+            status,
+            min_perf_pct,
+            max_perf_pct,
+            hwp_dynamic_boost,
+        })
+    }
+
+    /// returns the operation mode
+    pub fn status(&self) -> IntelPstateStatus {
+        self.status
+    }
+
+    /// returns the lower performance limit in percent
+    pub fn min_perf_pct(&self) -> Option<u32> {
+        self.min_perf_pct
+    }
+
+    /// returns the upper performance limit in percent
+    pub fn max_perf_pct(&self) -> Option<u32> {
+        self.max_perf_pct
+    }
+
+    /// returns whether HWP dynamic boost is enabled
+    pub fn hwp_dynamic_boost(&self) -> Option<bool> {
+        self.hwp_dynamic_boost
+    }
+
+    /// Switch the operation mode. This changes the scaling driver and the available governors
+    /// of all cores, so run discovery again afterwards.
+    pub fn set_status(&mut self, status: IntelPstateStatus) -> Result<()> {
+        debug!("Setting intel_pstate status {}", status);
+        write_attr(&self.dir.join("status"), status.as_str())
+    }
+
+    /// Set the lower performance limit in percent.
+    /// The kernel raises it to the lowest supported performance and lowers it to max_perf_pct.
+    pub fn set_min_perf_pct(&mut self, pct: u32) -> Result<()> {
+        debug!("Setting intel_pstate min_perf_pct {}", pct);
+        write_attr(
+            &self.dir.join("min_perf_pct"),
+            &validate_pct(pct)?.to_string(),
+        )
+    }
+
+    /// Set the upper performance limit in percent.
+    /// The kernel raises it to min_perf_pct.
+    pub fn set_max_perf_pct(&mut self, pct: u32) -> Result<()> {
+        debug!("Setting intel_pstate max_perf_pct {}", pct);
+        write_attr(
+            &self.dir.join("max_perf_pct"),
+            &validate_pct(pct)?.to_string(),
+        )
+    }
+
+    /// Enable or disable HWP dynamic boost
+    pub fn set_hwp_dynamic_boost(&mut self, enabled: bool) -> Result<()> {
+        debug!("Setting intel_pstate hwp_dynamic_boost {}", enabled);
+        write_attr(
+            &self.dir.join("hwp_dynamic_boost"),
+            if enabled { "1" } else { "0" },
+        )
+    }
+}
+
+fn validate_pct(pct: u32) -> Result<u32> {
+    if pct > 100 {
+        Err(Error::OutOfRange {
+            value: pct,
+            min: 0,
+            max: 100,
+        })
+    } else {
+        Ok(pct)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{discover_intel_pstate_at, IntelPstateStatus};
+    use fake::{install_emulation, FakeSysfs};
+    use {Core, Driver};
+
+    #[test]
+    fn intel_pstate() {
+        install_emulation();
+        let fake = FakeSysfs::builder().build().unwrap();
+        assert!(discover_intel_pstate_at(fake.root()).unwrap().is_none());
+
+        let fake = FakeSysfs::builder().intel_pstate("active").build().unwrap();
+        let mut p = discover_intel_pstate_at(fake.root()).unwrap().unwrap();
+        assert_eq!(p.status(), IntelPstateStatus::Active);
+        assert_eq!(p.max_perf_pct(), Some(100));
+        assert_eq!(p.hwp_dynamic_boost(), Some(false));
+
+        p.set_max_perf_pct(60).unwrap();
+        // clamped to max_perf_pct by the kernel
+        p.set_min_perf_pct(80).unwrap();
+        assert!(p.set_max_perf_pct(101).is_err());
+        let mut p = discover_intel_pstate_at(fake.root()).unwrap().unwrap();
+        assert_eq!(p.min_perf_pct(), Some(60));
+
+        p.set_status(IntelPstateStatus::Passive).unwrap();
+        let c = Core::discover(fake.path("cpu0")).unwrap();
+        assert_eq!(c.driver(), &Driver::IntelCpufreq);
+        assert!(c.available_govs().contains(&"schedutil".to_string()));
+        assert!("guided".parse::<IntelPstateStatus>().is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use {Error, Result};
+
+/// Device through which the kernel accepts PM QoS requests for the CPU wakeup latency
+pub const CPU_DMA_LATENCY: &str = "/dev/cpu_dma_latency";
+
+/// Largest latency the kernel accepts, which is also its default of no constraint
+const MAX_US: u32 = 2_000_000_000;
+
+/// A PM QoS request limiting the CPU wakeup latency, held until dropped.
+///
+/// While held, cpuidle only picks idle states with an exit latency of at most max_us on all cores,
+/// e.g. 0 keeps them out of all C-states but polling.
+/// Unlike disabling idle states this leaves no trace behind: the kernel drops the request
+/// as soon as the device is closed, even if the process crashes.
+#[derive(Debug)]
+pub struct LatencyHold {
+    /// The open device, closing it on drop releases the request
+    _file: File,
+    /// The requested latency limit in microseconds
+    max_us: u32,
+}
+
+impl LatencyHold {
+    /// Limit the wakeup latency to max_us microseconds through /dev/cpu_dma_latency.
+    /// Usually requires root.
+    pub fn new(max_us: u32) -> Result<LatencyHold> {
+        LatencyHold::new_at(CPU_DMA_LATENCY, max_us)
+    }
+
+    /// Limit the wakeup latency to max_us microseconds through the given device
+    pub fn new_at<P: AsRef<Path>>(device: P, max_us: u32) -> Result<LatencyHold> {
+        if max_us > MAX_US {
+            return Err(Error::OutOfRange {
+                value: max_us,
+                min: 0,
+                max: MAX_US,
+            });
+        }
+
+        let device = device.as_ref();
+        // the kernel expects the value as binary 32 bit integer
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(device)
+            .map_err(|e| Error::from_io(device, e))?;
+        file.write_all(&(max_us as i32).to_ne_bytes())
+            .map_err(|e| Error::from_write(device, &max_us.to_string(), e))?;
+        debug!(
+            "Holding CPU wakeup latency at {}us via {:?}",
+            max_us, device
+        );
+
+        Ok(LatencyHold {
+            _file: file,
+            max_us,
+        })
+    }
+
+    /// returns the requested latency limit in microseconds
+    pub fn max_us(&self) -> u32 {
+        self.max_us
+    }
+}
+
+impl Drop for LatencyHold {
+    fn drop(&mut self) {
+        debug!("Releasing CPU wakeup latency hold of {}us", self.max_us);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LatencyHold;
+    use fake::FakeSysfs;
+    use std::fs;
+    use Error;
+
+    #[test]
+    fn hold() {
+        let fake = FakeSysfs::builder().build().unwrap();
+        let device = fake.path("../../../dev/cpu_dma_latency");
+        fake.write(&device, "").unwrap();
+
+        let hold = LatencyHold::new_at(&device, 20).unwrap();
+        assert_eq!(hold.max_us(), 20);
+        assert_eq!(&fs::read(&device).unwrap()[..4], &20i32.to_ne_bytes());
+        drop(hold);
+
+        assert!(matches!(
+            LatencyHold::new_at(&device, u32::MAX),
+            Err(Error::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            LatencyHold::new_at(fake.path("../../../dev/missing"), 0),
+            Err(Error::NotSupported { .. })
+        ));
+    }
+}
@@ -5,51 +5,286 @@ extern crate serde;
 extern crate serde_derive;
 
 use std::fs;
-use std::io;
-use std::io::{Error, ErrorKind, Write};
-use std::path::PathBuf;
-
-macro_rules! parse_num {
-    ($g:ident, $op:expr) => {{
-        let mut chars = fs::read_to_string($g.join($op))?;
-        chars.retain(|c| c.is_digit(10));
-
-        match chars.parse() {
-            Ok(x) => x,
-            Err(e) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("Cause{}", e),
-                ));
-            }
-        }
-    }};
+use std::path::{Path, PathBuf};
+
+mod amd_pstate;
+mod backend;
+mod boost;
+mod cpuidle;
+mod cpuset;
+mod driver;
+mod epb;
+mod error;
+#[cfg(any(test, feature = "fake-sysfs"))]
+pub mod fake;
+mod freq;
+mod hotplug;
+mod hybrid;
+mod intel_pstate;
+mod latency;
+mod policy;
+mod stats;
+mod thermal;
+mod topology;
+mod tunables;
+
+pub use amd_pstate::{
+    discover_amd_pstate, discover_amd_pstate_at, AmdPstate, AmdPstateCore, AmdPstateStatus,
+};
+pub use backend::{set_backend, Backend, Sysfs};
+pub use boost::{boost_enabled, boost_enabled_at, set_boost, set_boost_at};
+pub use cpuidle::{discover_cpuidle, discover_cpuidle_at, CpuIdle, IdleState};
+pub use cpuset::CpuSet;
+pub use driver::Driver;
+pub use epb::EnergyPerfBias;
+pub use error::{Error, Result};
+pub use freq::{Frequency, Snap};
+pub use hotplug::{is_hotpluggable, is_hotpluggable_at, set_online, set_online_at};
+pub use hybrid::{CapacityTier, CoreKind};
+pub use intel_pstate::{
+    discover_intel_pstate, discover_intel_pstate_at, IntelPstate, IntelPstateStatus,
+};
+pub use latency::{LatencyHold, CPU_DMA_LATENCY};
+pub use policy::{discover_policies, discover_policies_at, Policy};
+pub use stats::{FreqStats, Residency, TransTable};
+pub use thermal::{
+    discover_thermal, discover_thermal_at, CoolingDevice, Thermal, ThermalZone, TripPoint,
+    SYSFS_THERMAL_ROOT,
+};
+pub use topology::Topology;
+pub use tunables::{discover_tunables, discover_tunables_at, GovernorTunables};
+
+/// Read a sysfs attribute, stripping control characters such as the trailing newline
+fn read_attr(path: &Path) -> Result<String> {
+    let mut chars = fs::read_to_string(path).map_err(|e| Error::from_io(path, e))?;
+    chars.retain(|c| !c.is_control());
+    Ok(chars)
+}
+
+/// Read a sysfs attribute containing a single unsigned number.
+/// Anything else, e.g. "-1" or "12abc", fails with [Error::Parse].
+fn read_num(path: &Path) -> Result<u32> {
+    let content = read_attr(path)?;
+    content.trim().parse().map_err(|_| Error::Parse {
+        path: path.to_path_buf(),
+        content,
+    })
+}
+
+/// Read a list of cores in the kernel's cpulist format, e.g. "0-3,8", or separated by spaces as in related_cpus
+fn read_cpulist(path: &Path) -> Result<CpuSet> {
+    let content = read_attr(path)?;
+    content.parse().map_err(|_| Error::Parse {
+        path: path.to_path_buf(),
+        content,
+    })
+}
+
+/// Turn a missing or unreadable attribute into None, e.g. for attributes only some drivers provide
+fn optional<T>(res: Result<T>) -> Result<Option<T>> {
+    match res {
+        Ok(t) => Ok(Some(t)),
+        Err(Error::NotSupported { .. }) | Err(Error::PermissionDenied { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write the value to a sysfs attribute through the installed [Backend]
+fn write_attr(path: &Path, value: &str) -> Result<()> {
+    backend::backend()
+        .write(path, value)
+        .map_err(|e| Error::from_write(path, value, e))
 }
 
+/// Default location of the cpu directories in sysfs
+pub const SYSFS_CPU_ROOT: &str = "/sys/devices/system/cpu";
+
 /// find relevant sysfs folders in /sys/devices/system/cpu/cpu<x>
-pub fn discover_core_settings() -> io::Result<Vec<Core>> {
-    let cpu_root = fs::read_dir("/sys/devices/system/cpu/")?;
-    debug!("Content of /sys/devices/system/cpu/  {:#?}", cpu_root);
+/// Cores which are offline or lack cpufreq are left out, use [discover] to find out about those.
+pub fn discover_core_settings() -> Result<Vec<Core>> {
+    discover_core_settings_at(SYSFS_CPU_ROOT)
+}
 
-    let is_core = |p: &fs::DirEntry| {
-        let f = p
-            .file_name()
-            .into_string()
-            .expect("Encountered invalid path while discovering core directories");
-        f.contains("cpu") && !(f.contains("cpuidle") || f.contains("cpufreq"))
+/// find relevant sysfs folders in <root>/cpu<x>
+/// The root replaces /sys/devices/system/cpu, e.g. to operate on a chroot, a bind mount or a fake tree.
+/// All reads and writes of the discovered [Core]s go through this root.
+/// Cores which are offline or lack cpufreq are left out, use [discover_at] to find out about those.
+pub fn discover_core_settings_at<P: AsRef<Path>>(root: P) -> Result<Vec<Core>> {
+    discover_at(root).map(Discovery::into_cores)
+}
+
+/// discover the online cores in /sys/devices/system/cpu/cpu<x> and the entries which had to be skipped
+pub fn discover() -> Result<Discovery> {
+    discover_at(SYSFS_CPU_ROOT)
+}
+
+/// discover the online cores in <root>/cpu<x> and the entries which had to be skipped
+/// Cores which fail to be read end up in the skipped entries, only failures at the root are returned.
+pub fn discover_at<P: AsRef<Path>>(root: P) -> Result<Discovery> {
+    let root = root.as_ref();
+    let cpu_root = fs::read_dir(root).map_err(|e| Error::from_io(root, e))?;
+    debug!("Content of {:?}  {:#?}", root, cpu_root);
+
+    let mut d = Discovery {
+        cores: Vec::new(),
+        skipped: Vec::new(),
+        present: optional(read_cpulist(&root.join("present")))?.unwrap_or_default(),
+        possible: optional(read_cpulist(&root.join("possible")))?.unwrap_or_default(),
+        online: optional(read_cpulist(&root.join("online")))?.unwrap_or_default(),
+        policies: discover_policies_at(root)?,
     };
 
-    cpu_root
-        // WARN: error cases described by read dir seem unrealistic at first so we're gonna ignore them
-        .filter_map(|e| e.ok())
-        .filter(|p| is_core(p))
-        .map(|p| p.path())
-        .inspect(|c| debug!("Found core: {:?}", c))
-        .try_fold(Vec::new(), |mut cores, c| {
-            let c = Core::discover(c)?;
-            cores.push(c);
-            Ok(cores)
-        })
+    // capacities of all cores including skipped ones, so tiers do not change with the online cores
+    let mut capacities = Vec::new();
+
+    // WARN: error cases described by read dir seem unrealistic at first so we're gonna ignore them
+    for entry in cpu_root.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+
+        // only cpu<x>, not cpufreq, cpuidle and friends
+        let num = match name.strip_prefix("cpu") {
+            Some(n) if n.starts_with(|c: char| c.is_ascii_digit()) => n,
+            _ => continue,
+        };
+        debug!("Found core: {:?}", path);
+        if let Ok(Some(capacity)) = optional(read_num(&path.join("cpu_capacity"))) {
+            capacities.push(capacity);
+        }
+
+        // cpus which cannot be hot-plugged (like cpu0 on x86) have no online file
+        let reason = if num.parse::<u32>().is_err() {
+            Some(SkipReason::Unparseable)
+        } else {
+            match optional(read_num(&path.join("online"))) {
+                Ok(Some(0)) => Some(SkipReason::Offline),
+                Err(e) => Some(SkipReason::Failed(e.to_string())),
+                Ok(_) if !path.join("cpufreq").is_dir() => Some(SkipReason::NoCpufreq),
+                Ok(_) => match Core::discover(path.clone()) {
+                    Ok(c) => {
+                        d.cores.push(c);
+                        None
+                    }
+                    Err(e) => {
+                        warn!("Skipping {:?}: {}", path, e);
+                        Some(SkipReason::Failed(e.to_string()))
+                    }
+                },
+            }
+        };
+
+        if let Some(reason) = reason {
+            debug!("Skipping {:?}: {:?}", path, reason);
+            d.skipped.push(Skipped { path, reason });
+        }
+    }
+    d.cores.sort_by_key(|c| c.num());
+    for c in d.cores.iter_mut() {
+        c.capacity_tier = c
+            .cpu_capacity
+            .and_then(|capacity| CapacityTier::classify(capacity, &capacities));
+    }
+    d.skipped.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(d)
+}
+
+/// Why an entry in the cpu directory was left out of the discovered cores
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkipReason {
+    /// The core is offline
+    Offline,
+    /// The core has no cpufreq directory, e.g. on some VMs
+    NoCpufreq,
+    /// The name of the entry could not be parsed
+    Unparseable,
+    /// Reading the core failed, e.g. an attribute could not be parsed or read.
+    /// Holds the error message.
+    Failed(String),
+}
+
+/// An entry in the cpu directory which was left out of the discovered cores
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Skipped {
+    /// Path to the entry
+    path: PathBuf,
+    /// Why it was skipped
+    reason: SkipReason,
+}
+
+impl Skipped {
+    /// returns the path of the skipped entry
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// returns why the entry was skipped
+    pub fn reason(&self) -> &SkipReason {
+        &self.reason
+    }
+}
+
+/// Result of [discover_at]: the online cores with cpufreq support and the state of the cpu directory
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Discovery {
+    /// Online cores with cpufreq support, ordered by number
+    cores: Vec<Core>,
+    /// Entries which were left out
+    skipped: Vec<Skipped>,
+    /// Cores present in the system (empty if the kernel does not tell)
+    present: CpuSet,
+    /// Cores which could ever be present (empty if the kernel does not tell)
+    possible: CpuSet,
+    /// Cores currently online (empty if the kernel does not tell)
+    online: CpuSet,
+    /// cpufreq policies ordered by number (empty on kernels without policy directories)
+    policies: Vec<Policy>,
+}
+
+impl Discovery {
+    /// returns the discovered cores
+    pub fn cores(&self) -> &[Core] {
+        self.cores.as_ref()
+    }
+
+    /// returns the discovered cores, consuming the discovery
+    pub fn into_cores(self) -> Vec<Core> {
+        self.cores
+    }
+
+    /// returns the entries which were left out
+    pub fn skipped(&self) -> &[Skipped] {
+        self.skipped.as_ref()
+    }
+
+    /// returns the cores present in the system
+    pub fn present(&self) -> &CpuSet {
+        &self.present
+    }
+
+    /// returns the cores which could ever be present
+    pub fn possible(&self) -> &CpuSet {
+        &self.possible
+    }
+
+    /// returns the cores currently online
+    pub fn online(&self) -> &CpuSet {
+        &self.online
+    }
+
+    /// returns the cpufreq policies, each with the discovered cores it governs.
+    /// Empty on kernels without policy directories.
+    pub fn policies(&self) -> Vec<(&Policy, Vec<&Core>)> {
+        self.policies
+            .iter()
+            .map(|p| {
+                (
+                    p,
+                    self.cores.iter().filter(|c| p.contains(c.num())).collect(),
+                )
+            })
+            .collect()
+    }
 }
 
 /// Representation of the current cpufrequency serttings of a single core
@@ -60,50 +295,138 @@ pub struct Core {
     core: PathBuf,
     /// Number of the core
     num: u32,
-    /// CPU Minimum Frequency
-    cpuinfo_max_freq: u32,
     /// CPU Maximum Frequency
-    cpuinfo_min_freq: u32,
+    cpuinfo_max_freq: Frequency,
+    /// CPU Minimum Frequency
+    cpuinfo_min_freq: Frequency,
     /// List of possible values for the governor
     scaling_available_governors: Vec<String>,
     /// Current upper frequency limit - the governor may increase frequency up to this value
-    scaling_max_freq: u32,
+    scaling_max_freq: Frequency,
     /// Current lower frequency limit - the governor may reduce the frequency down to this value
-    scaling_min_freq: u32,
+    scaling_min_freq: Frequency,
     /// Currently set scaling governor
     scaling_governor: String,
+    /// Current frequency as last requested by cpufreq (or reported by the driver)
+    scaling_cur_freq: Frequency,
+    /// Current frequency as read from the hardware, only readable by root and not provided by all drivers
+    cpuinfo_cur_freq: Option<Frequency>,
+    /// Discrete frequency steps published by the driver in ascending order, empty if the driver has none
+    scaling_available_frequencies: Vec<Frequency>,
+    /// The cpufreq driver in charge of the core
+    scaling_driver: Driver,
+    /// Number of the cpufreq policy governing the core, None on kernels without policy directories
+    policy: Option<u32>,
+    /// Placement of the core in the cpu topology, None if the kernel does not provide it
+    topology: Option<Topology>,
+    /// Performance or efficiency core on hybrid CPUs
+    kind: CoreKind,
+    /// Relative compute capacity (the biggest cores have 1024) on heterogeneous ARM CPUs
+    cpu_capacity: Option<u32>,
+    /// Tier of cpu_capacity among all cores, assigned by [discover_at]
+    capacity_tier: Option<CapacityTier>,
+    /// amd-pstate specific attributes, None with other drivers
+    amd_pstate: Option<AmdPstateCore>,
+    /// Energy performance preference (EPP), None if the driver or hardware does not support it
+    energy_performance_preference: Option<String>,
+    /// Named EPP values accepted by the driver
+    energy_performance_available_preferences: Vec<String>,
+    /// Intel energy performance bias (EPB), None on CPUs without it
+    energy_perf_bias: Option<EnergyPerfBias>,
+    /// Frequency pinned through the userspace governor, None with other governors
+    scaling_setspeed: Option<Frequency>,
 }
 
 impl Core {
     /// discover settings for the core specified by its path
-    pub fn discover(core: PathBuf) -> io::Result<Core> {
+    pub fn discover(core: PathBuf) -> Result<Core> {
         let g = core.join("cpufreq");
 
-        let cpuinfo_min_freq: u32 = parse_num!(g, "cpuinfo_min_freq");
-        let cpuinfo_max_freq: u32 = parse_num!(g, "cpuinfo_max_freq");
-        let scaling_min_freq: u32 = parse_num!(g, "scaling_min_freq");
-        let scaling_max_freq: u32 = parse_num!(g, "scaling_max_freq");
-        let scaling_governor = {
-            let mut chars = fs::read_to_string(g.join("scaling_governor"))?;
-            chars.retain(|c| !c.is_control());
-            chars
-        };
+        let cpuinfo_min_freq = Frequency::from_khz(read_num(&g.join("cpuinfo_min_freq"))?);
+        let cpuinfo_max_freq = Frequency::from_khz(read_num(&g.join("cpuinfo_max_freq"))?);
+        let scaling_min_freq = Frequency::from_khz(read_num(&g.join("scaling_min_freq"))?);
+        let scaling_max_freq = Frequency::from_khz(read_num(&g.join("scaling_max_freq"))?);
+        let scaling_cur_freq = Frequency::from_khz(read_num(&g.join("scaling_cur_freq"))?);
+        let cpuinfo_cur_freq =
+            optional(read_num(&g.join("cpuinfo_cur_freq")))?.map(Frequency::from_khz);
+        let scaling_governor = read_attr(&g.join("scaling_governor"))?;
+        let scaling_driver = Driver::from(read_attr(&g.join("scaling_driver"))?);
 
-        let scaling_available_governors = {
-            let mut chars = fs::read_to_string(g.join("scaling_available_governors"))?;
-            chars.retain(|c| !c.is_control());
-            chars.split(&" ").map(|s| s.into()).collect()
-        };
+        let mut scaling_available_frequencies =
+            optional(read_attr(&g.join("scaling_available_frequencies")))?
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(|f| match f.parse() {
+                    Ok(f) => Ok(Frequency::from_khz(f)),
+                    Err(_) => Err(Error::Parse {
+                        path: g.join("scaling_available_frequencies"),
+                        content: f.into(),
+                    }),
+                })
+                .collect::<Result<Vec<_>>>()?;
+        scaling_available_frequencies.sort();
+
+        let scaling_available_governors = read_attr(&g.join("scaling_available_governors"))?
+            .split_whitespace()
+            .map(|s| s.into())
+            .collect();
+
+        // cpu<x>/cpufreq links to cpufreq/policy<y>
+        let policy = fs::canonicalize(&g)
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .and_then(|n| {
+                if n.starts_with("policy") {
+                    n.trim_start_matches("policy").parse().ok()
+                } else {
+                    None
+                }
+            });
 
         // parse the number
-        let num = core
-            .to_str()
-            .expect("Failed to convert PathBuf to String")
-            .rsplit("cpu")
-            .next()
-            .expect("Core name did not contain 'cpu'")
-            .parse()
-            .expect("Failed to parse the u32 core number");
+        let name = core
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let num = match name.rsplit("cpu").next().map(|n| n.parse()) {
+            Some(Ok(num)) if name.starts_with("cpu") => num,
+            _ => {
+                return Err(Error::Parse {
+                    path: core,
+                    content: name,
+                })
+            }
+        };
+
+        let topology = Topology::discover(&core)?;
+        let cpu_capacity = optional(read_num(&core.join("cpu_capacity")))?;
+        let amd_pstate = AmdPstateCore::discover(&g)?;
+        let epb_file = core.join("power/energy_perf_bias");
+        let energy_perf_bias = match optional(read_num(&epb_file))? {
+            Some(raw) => Some(EnergyPerfBias::from_raw(raw).map_err(|_| Error::Parse {
+                path: epb_file,
+                content: raw.to_string(),
+            })?),
+            None => None,
+        };
+        let energy_performance_preference =
+            optional(read_attr(&g.join("energy_performance_preference")))?;
+        let energy_performance_available_preferences = optional(read_attr(
+            &g.join("energy_performance_available_preferences"),
+        ))?
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|s| s.into())
+        .collect();
+        // reads <unsupported> unless the userspace governor is applied
+        let scaling_setspeed = optional(read_attr(&g.join("scaling_setspeed")))?
+            .and_then(|s| s.parse().ok())
+            .map(Frequency::from_khz);
+        let kind = match core.parent() {
+            Some(root) => CoreKind::discover(root, num)?,
+            None => CoreKind::Unknown,
+        };
 
         let c = Core {
             core,
@@ -114,6 +437,20 @@ impl Core {
             scaling_governor,
             scaling_min_freq,
             scaling_max_freq,
+            scaling_cur_freq,
+            cpuinfo_cur_freq,
+            scaling_available_frequencies,
+            scaling_driver,
+            policy,
+            topology,
+            kind,
+            cpu_capacity,
+            capacity_tier: None,
+            amd_pstate,
+            energy_performance_preference,
+            energy_performance_available_preferences,
+            energy_perf_bias,
+            scaling_setspeed,
         };
         debug!("Read settings : {:#?}", c);
 
@@ -125,70 +462,166 @@ impl Core {
         self.num
     }
 
-    /// returns cpu minimum frequency in kHz
-    pub fn cpu_min(&self) -> u32 {
+    /// returns the number of the cpufreq [Policy] governing the core.
+    /// Limits and governor are shared with all cores of the policy.
+    pub fn policy(&self) -> Option<u32> {
+        self.policy
+    }
+
+    /// returns the placement of the core in the cpu topology
+    pub fn topology(&self) -> Option<&Topology> {
+        self.topology.as_ref()
+    }
+
+    /// returns whether this is a performance or efficiency core on hybrid CPUs
+    pub fn kind(&self) -> CoreKind {
+        self.kind
+    }
+
+    /// returns the relative compute capacity as read from cpu<x>/cpu_capacity, the biggest cores have 1024
+    pub fn capacity(&self) -> Option<u32> {
+        self.cpu_capacity
+    }
+
+    /// returns the capacity tier among all cores of the system.
+    /// None if all cores have the same capacity, the kernel does not provide it
+    /// or the core was discovered on its own via [Core::discover].
+    pub fn tier(&self) -> Option<CapacityTier> {
+        self.capacity_tier
+    }
+
+    /// returns the amd-pstate specific attributes like the preferred core ranking
+    pub fn amd_pstate(&self) -> Option<&AmdPstateCore> {
+        self.amd_pstate.as_ref()
+    }
+
+    /// returns the energy performance preference (EPP), a name or a raw value
+    pub fn epp(&self) -> Option<&str> {
+        self.energy_performance_preference.as_deref()
+    }
+
+    /// returns the named EPP values accepted by the driver
+    pub fn available_epps(&self) -> &[String] {
+        self.energy_performance_available_preferences.as_ref()
+    }
+
+    /// returns the Intel energy performance bias (EPB)
+    pub fn epb(&self) -> Option<EnergyPerfBias> {
+        self.energy_perf_bias
+    }
+
+    /// returns the frequency pinned through the userspace governor
+    pub fn speed(&self) -> Option<Frequency> {
+        self.scaling_setspeed
+    }
+
+    /// Read the idle states (C-states), shallowest first; empty without cpuidle.
+    /// They are not part of the discovered settings, so a broken cpuidle attribute only fails this call.
+    pub fn idle_states(&self) -> Result<Vec<IdleState>> {
+        IdleState::discover_all(&self.core)
+    }
+
+    /// returns cpu minimum frequency
+    pub fn cpu_min(&self) -> Frequency {
         self.cpuinfo_min_freq
     }
 
-    /// returns cpu maximum frequency in kHz
-    pub fn cpu_max(&self) -> u32 {
+    /// returns cpu maximum frequency
+    pub fn cpu_max(&self) -> Frequency {
         self.cpuinfo_max_freq
     }
 
-    /// returns current min scaling frequency in kHz
-    pub fn curr_min(&self) -> u32 {
+    /// returns current min scaling frequency
+    pub fn curr_min(&self) -> Frequency {
         self.scaling_min_freq
     }
 
-    /// returns current max scaling frequency in kHz
-    pub fn curr_max(&self) -> u32 {
+    /// returns current max scaling frequency
+    pub fn curr_max(&self) -> Frequency {
         self.scaling_max_freq
     }
 
+    /// returns the current operating frequency as seen by cpufreq
+    pub fn curr_freq(&self) -> Frequency {
+        self.scaling_cur_freq
+    }
+
+    /// returns the current operating frequency as read from the hardware, if the driver supports it and it is readable
+    pub fn cpu_freq(&self) -> Option<Frequency> {
+        self.cpuinfo_cur_freq
+    }
+
     /// returns the current governor
     pub fn curr_gov(&self) -> &str {
         self.scaling_governor.as_ref()
     }
 
+    /// returns the scaling driver
+    pub fn driver(&self) -> &Driver {
+        &self.scaling_driver
+    }
+
     /// returns available governors
     pub fn available_govs(&self) -> &[String] {
         self.scaling_available_governors.as_ref()
     }
 
-    /// Validate the given minimum value. Must be >= the discovered CPU frequency minimum
-    pub fn validate_min(&self, freq: u32) -> io::Result<u32> {
+    /// returns the discrete frequency steps supported by the driver in ascending order.
+    /// Empty if the driver does not publish scaling_available_frequencies (e.g. intel_pstate).
+    pub fn available_freqs(&self) -> &[Frequency] {
+        self.scaling_available_frequencies.as_ref()
+    }
+
+    /// Round the frequency to one of the [Core::available_freqs]. Returns freq unchanged if there are none.
+    pub fn snap(&self, freq: Frequency, snap: Snap) -> Frequency {
+        snap.apply(freq, &self.scaling_available_frequencies)
+    }
+
+    /// Validate the given minimum value. Must be >= the discovered CPU frequency minimum and <= the current max
+    pub fn validate_min(&self, freq: Frequency) -> Result<Frequency> {
         if self.cpuinfo_min_freq <= freq && freq <= self.scaling_max_freq {
             Ok(freq)
         } else {
-            Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!(
-                    "Min Frequency {} not in ({},{}]",
-                    freq, self.cpuinfo_min_freq, self.scaling_max_freq
-                ),
-            ))
+            Err(Error::FrequencyOutOfRange {
+                value: freq,
+                min: self.cpuinfo_min_freq,
+                max: self.scaling_max_freq,
+            })
         }
     }
 
     /// Validate the given maximum value. Must be >= current min and <= CPU frequency maximum
-    pub fn validate_max(&self, freq: u32) -> io::Result<u32> {
-        if (self.cpuinfo_min_freq <= freq && self.scaling_min_freq <= freq)
-            && freq <= self.cpuinfo_max_freq
-        {
+    pub fn validate_max(&self, freq: Frequency) -> Result<Frequency> {
+        let min = self.cpuinfo_min_freq.max(self.scaling_min_freq);
+        if min <= freq && freq <= self.cpuinfo_max_freq {
             Ok(freq)
         } else {
-            Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!(
-                    "Max Frequency {} not in min({},{})..={}",
-                    freq, self.cpuinfo_min_freq, self.scaling_min_freq, self.cpuinfo_max_freq
-                ),
-            ))
+            Err(Error::FrequencyOutOfRange {
+                value: freq,
+                min,
+                max: self.cpuinfo_max_freq,
+            })
         }
     }
 
+    /// Validate the given minimum value like [Core::validate_min] and round it to one of the [Core::available_freqs].
+    /// Returns the frequency that will actually be applied, which must not exceed the current max either.
+    /// The kernel rounds up, i.e. [Snap::Up].
+    pub fn validate_min_snapped(&self, freq: Frequency, snap: Snap) -> Result<Frequency> {
+        let freq = self.validate_min(freq)?;
+        self.validate_min(self.snap(freq, snap))
+    }
+
+    /// Validate the given maximum value like [Core::validate_max] and round it to one of the [Core::available_freqs].
+    /// Returns the frequency that will actually be applied, which must not fall below the current min either.
+    /// The kernel rounds down, i.e. [Snap::Down].
+    pub fn validate_max_snapped(&self, freq: Frequency, snap: Snap) -> Result<Frequency> {
+        let freq = self.validate_max(freq)?;
+        self.validate_max(self.snap(freq, snap))
+    }
+
     /// Validate the governor by checking against the list of available governors
-    pub fn validate_governor<'a>(&self, governor: &'a str) -> io::Result<&'a str> {
+    pub fn validate_governor<'a>(&self, governor: &'a str) -> Result<&'a str> {
         if self
             .scaling_available_governors
             .iter()
@@ -196,71 +629,214 @@ impl Core {
         {
             Ok(governor)
         } else {
-            Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!(
-                    "Governor {} not available. Must be one of {:?}",
-                    governor, self.scaling_available_governors
+            Err(Error::UnknownGovernor {
+                governor: governor.into(),
+                available: self.scaling_available_governors.clone(),
+            })
+        }
+    }
+
+    /// Validate a frequency to pin the core to. Must be within the CPU frequency range and,
+    /// if the driver has discrete steps, one of the [Core::available_freqs].
+    pub fn validate_speed(&self, freq: Frequency) -> Result<Frequency> {
+        if freq < self.cpuinfo_min_freq || freq > self.cpuinfo_max_freq {
+            return Err(Error::FrequencyOutOfRange {
+                value: freq,
+                min: self.cpuinfo_min_freq,
+                max: self.cpuinfo_max_freq,
+            });
+        }
+        if !self.scaling_available_frequencies.is_empty()
+            && !self.scaling_available_frequencies.contains(&freq)
+        {
+            return Err(Error::InvalidInput {
+                input: freq.to_string(),
+                expected: format!(
+                    "one of {}",
+                    self.scaling_available_frequencies
+                        .iter()
+                        .map(|f| f.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            });
+        }
+        Ok(freq)
+    }
+
+    /// Validate the energy performance preference: either one of the available names
+    /// or, if the driver accepts them (see [Driver::accepts_raw_epp]), a raw value in 0..=255
+    /// (0 favours performance, 255 energy efficiency).
+    /// Fails with [Error::NotSupported] if the core has no EPP.
+    pub fn validate_epp<'a>(&self, epp: &'a str) -> Result<&'a str> {
+        if self.energy_performance_preference.is_none() {
+            return Err(Error::NotSupported {
+                path: self.core.join("cpufreq/energy_performance_preference"),
+            });
+        }
+
+        let raw = self.scaling_driver.accepts_raw_epp();
+        match epp.parse::<u32>() {
+            Ok(value) if raw && value > 255 => Err(Error::OutOfRange {
+                value,
+                min: 0,
+                max: 255,
+            }),
+            Ok(_) if raw => Ok(epp),
+            _ if self
+                .energy_performance_available_preferences
+                .iter()
+                .any(|e| e == epp) =>
+            {
+                Ok(epp)
+            }
+            _ => Err(Error::InvalidInput {
+                input: epp.into(),
+                expected: format!(
+                    "one of {}{}",
+                    self.energy_performance_available_preferences.join(", "),
+                    if raw { " or 0-255" } else { "" }
                 ),
-            ))
+            }),
         }
     }
 
     /// Set the minimum scaling frequency (lower frequency limit)
     /// This operation is not checked by mediocore, but the kernel may refuse to accept certain inputs.  
     /// Use [Core::validate_min] on the value beforehand.
-    pub fn set_min(&mut self, freq: u32) -> io::Result<()> {
+    pub fn set_min(&mut self, freq: Frequency) -> Result<()> {
         debug!("Setting minimum scaling frequency {} on {}", freq, self.num);
-        let mut f = fs::OpenOptions::new()
-            .write(true)
-            .open(self.core.join("cpufreq/scaling_min_freq"))?;
-        f.write_all(format!("{}", freq).as_ref())?;
-        Ok(())
+        write_attr(
+            &self.core.join("cpufreq/scaling_min_freq"),
+            &freq.as_khz().to_string(),
+        )
     }
 
     /// Set the maximum scaling frequency (lower frequency limit)  
     /// This operation is not checked by mediocore, but the kernel may refuse to accept certain inputs.  
     /// Use [Core::validate_max] on the value beforehand.
-    pub fn set_max(&mut self, freq: u32) -> io::Result<()> {
+    pub fn set_max(&mut self, freq: Frequency) -> Result<()> {
         debug!("Setting maximum scaling frequency {} on {}", freq, self.num);
-        let mut f = fs::OpenOptions::new()
-            .write(true)
-            .open(self.core.join("cpufreq/scaling_max_freq"))?;
-        f.write_all(format!("{}", freq).as_ref())?;
-        Ok(())
+        write_attr(
+            &self.core.join("cpufreq/scaling_max_freq"),
+            &freq.as_khz().to_string(),
+        )
+    }
+
+    /// Take the core offline or bring it back online, see [set_online_at].
+    /// An offline core is left out by discovery, so run it again afterwards.
+    pub fn set_online(&mut self, online: bool) -> Result<()> {
+        let root = self
+            .core
+            .parent()
+            .unwrap_or_else(|| Path::new(SYSFS_CPU_ROOT));
+        set_online_at(root, self.num, online)
     }
 
     /// Apply the given governor
     /// This operation is not checked by mediocore, but the kernel may refuse to accept certain inputs.
     /// Use [Core::validate_governor] on the value beforehand.
-    pub fn set_governor(&mut self, guvnor: &str) -> io::Result<()> {
+    pub fn set_governor(&mut self, guvnor: &str) -> Result<()> {
         debug!("Setting governor {} on {}", guvnor, self.num);
-        fs::OpenOptions::new()
-            .write(true)
-            .read(false)
-            .open(self.core.join("cpufreq/scaling_governor"))
-            .and_then(|mut f| f.write_all(guvnor.as_bytes()))
-            .map(|_| ())
+        write_attr(&self.core.join("cpufreq/scaling_governor"), guvnor)
+    }
+
+    /// Pin the core to the given frequency, validated with [Core::validate_speed].
+    /// Only accepted while the userspace governor is applied; the kernel clamps the frequency
+    /// to the current scaling limits.
+    pub fn set_speed(&mut self, freq: Frequency) -> Result<()> {
+        let freq = self.validate_speed(freq)?;
+        debug!("Setting speed {} on {}", freq, self.num);
+        write_attr(
+            &self.core.join("cpufreq/scaling_setspeed"),
+            &freq.as_khz().to_string(),
+        )
+    }
+
+    /// Disable or re-enable an idle state given by name or index, e.g. C6 or 3.
+    /// Disabling deep states lowers wakeup latency at the cost of power.
+    pub fn set_idle_state_disabled(&mut self, state: &str, disabled: bool) -> Result<()> {
+        let num = self.num;
+        let mut states = self.idle_states()?;
+        let names = states
+            .iter()
+            .map(|s| s.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        match states.iter_mut().find(|s| s.is(state)) {
+            Some(s) => s.set_disabled(disabled),
+            None if names.is_empty() => Err(Error::NotSupported {
+                path: self.core.join("cpuidle"),
+            }),
+            None => Err(Error::InvalidInput {
+                input: state.into(),
+                expected: format!("an idle state of core {}: {}", num, names),
+            }),
+        }
+    }
+
+    /// Apply the given energy performance bias. Its range is checked when parsing or creating it.
+    pub fn set_epb(&mut self, epb: EnergyPerfBias) -> Result<()> {
+        debug!("Setting energy performance bias {} on {}", epb, self.num);
+        write_attr(
+            &self.core.join("power/energy_perf_bias"),
+            &epb.as_raw().to_string(),
+        )
+    }
+
+    /// Apply the given energy performance preference, a name or a raw value.
+    /// This operation is not checked by mediocore, but the kernel may refuse to accept certain inputs.
+    /// Use [Core::validate_epp] on the value beforehand.
+    pub fn set_epp(&mut self, epp: &str) -> Result<()> {
+        debug!(
+            "Setting energy performance preference {} on {}",
+            epp, self.num
+        );
+        write_attr(
+            &self.core.join("cpufreq/energy_performance_preference"),
+            epp,
+        )
     }
 }
 
 #[cfg(test)]
 mod test {
-    use io::{ErrorKind, Result};
+    use fake::{install_emulation, FakeSysfs};
     use std::path::PathBuf;
-    use Core;
+    use {
+        discover_at, discover_core_settings_at, Core, CoreKind, Driver, Error, Frequency, Result,
+        SkipReason, Snap,
+    };
+
+    fn khz(f: u32) -> Frequency {
+        Frequency::from_khz(f)
+    }
 
     #[test]
     fn freq_validation() {
         let s = Core {
             core: PathBuf::from("/sys/devices/system/cpu/cpu0"),
             num: 0,
-            cpuinfo_min_freq: 800000,
-            cpuinfo_max_freq: 2500000,
+            cpuinfo_min_freq: khz(800000),
+            cpuinfo_max_freq: khz(2500000),
             scaling_available_governors: vec![],
             scaling_governor: "".into(),
-            scaling_min_freq: 850000,
-            scaling_max_freq: 900000,
+            scaling_min_freq: khz(850000),
+            scaling_max_freq: khz(900000),
+            scaling_cur_freq: khz(850000),
+            cpuinfo_cur_freq: None,
+            scaling_available_frequencies: vec![],
+            scaling_driver: Driver::IntelPstate,
+            policy: Some(0),
+            topology: None,
+            kind: CoreKind::Unknown,
+            cpu_capacity: None,
+            capacity_tier: None,
+            amd_pstate: None,
+            energy_performance_preference: None,
+            energy_performance_available_preferences: vec![],
+            energy_perf_bias: None,
+            scaling_setspeed: None,
         };
 
         let check_val = |x, v| match x {
@@ -268,18 +844,18 @@ mod test {
             Err(_) => false,
         };
 
-        let check_err = |x: Result<u32>| match x {
-            Ok(_) => false,
-            Err(ref e) if e.kind() == ErrorKind::InvalidInput => true,
-            _ => false,
-        };
+        let check_err = |x: Result<Frequency>| matches!(x, Err(Error::FrequencyOutOfRange { .. }));
 
-        assert!(check_val(s.validate_min(800000), 800000));
-        assert!(check_val(s.validate_min(850000), 850000));
-        assert!(check_err(s.validate_min(1000000)));
+        assert!(check_val(s.validate_min(khz(800000)), khz(800000)));
+        assert!(check_val(s.validate_min(khz(850000)), khz(850000)));
+        assert!(check_err(s.validate_min(khz(1000000))));
 
-        assert!(check_val(s.validate_max(1000000), 1000000));
-        assert!(check_err(s.validate_max(8000000)));
+        assert!(check_val(s.validate_max(khz(1000000)), khz(1000000)));
+        assert!(check_err(s.validate_max(khz(8000000))));
+        assert_eq!(
+            s.validate_max(khz(8000000)).unwrap_err().to_string(),
+            "Frequency 8GHz not in 850MHz..=2.5GHz"
+        );
     }
 
     #[test]
@@ -287,16 +863,347 @@ mod test {
         let s = Core {
             core: PathBuf::from(&"/sys/devices/system/cpu/cpu0"),
             num: 0,
-            cpuinfo_min_freq: 800000,
-            cpuinfo_max_freq: 2500000,
+            cpuinfo_min_freq: khz(800000),
+            cpuinfo_max_freq: khz(2500000),
             scaling_available_governors: vec!["performance".into(), "powersave".into()],
             scaling_governor: "powersave".into(),
-            scaling_min_freq: 850000,
-            scaling_max_freq: 900000,
+            scaling_min_freq: khz(850000),
+            scaling_max_freq: khz(900000),
+            scaling_cur_freq: khz(850000),
+            cpuinfo_cur_freq: None,
+            scaling_available_frequencies: vec![],
+            scaling_driver: Driver::IntelPstate,
+            policy: Some(0),
+            topology: None,
+            kind: CoreKind::Unknown,
+            cpu_capacity: None,
+            capacity_tier: None,
+            amd_pstate: None,
+            energy_performance_preference: None,
+            energy_performance_available_preferences: vec![],
+            energy_perf_bias: None,
+            scaling_setspeed: None,
         };
 
-        assert!(s.validate_governor(&"performance").is_ok());
-        assert!(s.validate_governor(&"conservative").is_err());
+        assert!(s.validate_governor("performance").is_ok());
+        assert!(s.validate_governor("conservative").is_err());
+    }
+
+    #[test]
+    fn discovery_at_root() {
+        let fake = FakeSysfs::builder().cores(2).build().unwrap();
+
+        let mut cores = discover_core_settings_at(fake.root()).unwrap();
+        cores.sort_by_key(|c| c.num());
+        assert_eq!(cores.len(), 2);
+        assert_eq!(cores[1].num(), 1);
+        assert_eq!(cores[1].cpu_max(), khz(3000000));
+        assert_eq!(cores[1].curr_gov(), "powersave");
+        assert_eq!(cores[1].driver(), &Driver::IntelPstate);
+
+        cores[1].set_max(khz(1000000)).unwrap();
+        assert_eq!(
+            fake.read("cpu1/cpufreq/scaling_max_freq").unwrap(),
+            "1000000"
+        );
+        assert_eq!(
+            fake.read("cpu0/cpufreq/scaling_max_freq").unwrap(),
+            "3000000"
+        );
+    }
+
+    #[test]
+    fn fake_kernel_semantics() {
+        install_emulation();
+        let fake = FakeSysfs::builder()
+            .cores(1)
+            .cpuinfo(800000, 2500000)
+            .build()
+            .unwrap();
+        let mut c = discover_core_settings_at(fake.root()).unwrap().remove(0);
+
+        // values outside of the cpuinfo limits are clamped
+        c.set_max(khz(9000000)).unwrap();
+        assert_eq!(
+            fake.read("cpu0/cpufreq/scaling_max_freq").unwrap(),
+            "2500000"
+        );
+        c.set_min(khz(100)).unwrap();
+        assert_eq!(
+            fake.read("cpu0/cpufreq/scaling_min_freq").unwrap(),
+            "800000"
+        );
+
+        // a max below the current min is rejected
+        c.set_min(khz(1200000)).unwrap();
+        match c.set_max(khz(1000000)) {
+            Err(Error::Rejected { ref value, .. }) => assert_eq!(value, "1000000"),
+            r => panic!("Expected rejection, got {:?}", r),
+        }
+        assert_eq!(
+            fake.read("cpu0/cpufreq/scaling_max_freq").unwrap(),
+            "2500000"
+        );
+
+        // unknown governors are refused
+        match c.set_governor("ondemand") {
+            Err(Error::Rejected { .. }) => (),
+            r => panic!("Expected rejection, got {:?}", r),
+        }
+        c.set_governor("performance").unwrap();
+        assert_eq!(
+            fake.read("cpu0/cpufreq/scaling_governor").unwrap(),
+            "performance"
+        );
+    }
+
+    #[test]
+    fn typed_errors() {
+        let fake = FakeSysfs::builder().cores(2).build().unwrap();
+
+        fake.write("cpu0/cpufreq/cpuinfo_max_freq", "n/a").unwrap();
+        match Core::discover(fake.path("cpu0")) {
+            Err(Error::Parse { path, .. }) => {
+                assert_eq!(path, fake.path("cpu0/cpufreq/cpuinfo_max_freq"))
+            }
+            r => panic!("Expected parse error, got {:?}", r),
+        }
+
+        fake.remove("cpu1/cpufreq").unwrap();
+        match Core::discover(fake.path("cpu1")) {
+            Err(Error::NotSupported { .. }) => (),
+            r => panic!("Expected missing attribute, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn strict_numbers() {
+        let fake = FakeSysfs::builder().cores(1).build().unwrap();
+        fake.write("cpu0/cpufreq/scaling_cur_freq", " 1400000 ")
+            .unwrap();
+        assert_eq!(
+            Core::discover(fake.path("cpu0")).unwrap().curr_freq(),
+            khz(1400000)
+        );
+
+        for garbage in &["-1", "12abc", "1 2", ""] {
+            fake.write("cpu0/cpufreq/scaling_cur_freq", garbage)
+                .unwrap();
+            match Core::discover(fake.path("cpu0")) {
+                Err(Error::Parse { content, .. }) => assert_eq!(&content, garbage),
+                r => panic!("Expected parse error for {:?}, got {:?}", garbage, r),
+            }
+        }
+    }
+
+    #[test]
+    fn current_freq() {
+        let fake = FakeSysfs::builder().cores(1).build().unwrap();
+        fake.write("cpu0/cpufreq/scaling_cur_freq", "1400000")
+            .unwrap();
+
+        let c = Core::discover(fake.path("cpu0")).unwrap();
+        assert_eq!(c.curr_freq(), khz(1400000));
+        assert_eq!(c.cpu_freq(), Some(khz(800000)));
+
+        fake.remove("cpu0/cpufreq/cpuinfo_cur_freq").unwrap();
+        let c = Core::discover(fake.path("cpu0")).unwrap();
+        assert_eq!(c.cpu_freq(), None);
     }
 
+    #[test]
+    fn freq_steps() {
+        let fake = FakeSysfs::builder()
+            .cores(1)
+            .cpuinfo(800000, 2400000)
+            .frequencies(&[800000, 1600000, 2400000])
+            .build()
+            .unwrap();
+
+        let mut c = Core::discover(fake.path("cpu0")).unwrap();
+        assert_eq!(
+            c.available_freqs(),
+            &[khz(800000), khz(1600000), khz(2400000)]
+        );
+
+        // the kernel rounds min up and max down
+        assert_eq!(c.validate_min(khz(1000000)).unwrap(), khz(1000000));
+        assert_eq!(
+            c.validate_min_snapped(khz(1000000), Snap::Up).unwrap(),
+            khz(1600000)
+        );
+        assert_eq!(
+            c.validate_max_snapped(khz(2000000), Snap::Down).unwrap(),
+            khz(1600000)
+        );
+        assert_eq!(
+            c.validate_max_snapped(khz(2400000), Snap::Down).unwrap(),
+            khz(2400000)
+        );
+
+        // the rounded value has to fit the opposite limit as well
+        c.set_max(khz(1200000)).unwrap();
+        let c = Core::discover(fake.path("cpu0")).unwrap();
+        assert!(c.validate_min(khz(1000000)).is_ok());
+        assert!(matches!(
+            c.validate_min_snapped(khz(1000000), Snap::Up),
+            Err(Error::FrequencyOutOfRange { value, .. }) if value == khz(1600000)
+        ));
+        assert_eq!(
+            c.validate_min_snapped(khz(1000000), Snap::Down).unwrap(),
+            khz(800000)
+        );
+    }
+
+    #[test]
+    fn skipped_entries() {
+        let fake = FakeSysfs::builder().cores(7).offline(&[2]).build().unwrap();
+        fake.remove("cpu3/cpufreq").unwrap();
+        fake.write("cpu4/cpufreq/scaling_min_freq", "n/a").unwrap();
+        fake.write("cpu5/online", "yes").unwrap();
+        fake.remove("cpu6/cpufreq/cpuinfo_max_freq").unwrap();
+        fake.write("cpu7x/online", "1").unwrap();
+        fake.write("cpuidle/current_driver", "none").unwrap();
+
+        let d = discover_at(fake.root()).unwrap();
+        assert_eq!(
+            d.cores().iter().map(|c| c.num()).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(
+            d.skipped()
+                .iter()
+                .map(|s| (s.path().to_path_buf(), s.reason().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (fake.path("cpu2"), SkipReason::Offline),
+                (fake.path("cpu3"), SkipReason::NoCpufreq),
+                (
+                    fake.path("cpu4"),
+                    SkipReason::Failed(format!(
+                        "Failed to parse \"n/a\" read from {}",
+                        fake.path("cpu4/cpufreq/scaling_min_freq").display()
+                    ))
+                ),
+                (
+                    fake.path("cpu5"),
+                    SkipReason::Failed(format!(
+                        "Failed to parse \"yes\" read from {}",
+                        fake.path("cpu5/online").display()
+                    ))
+                ),
+                (
+                    fake.path("cpu6"),
+                    SkipReason::Failed(format!(
+                        "{} does not exist",
+                        fake.path("cpu6/cpufreq/cpuinfo_max_freq").display()
+                    ))
+                ),
+                (fake.path("cpu7x"), SkipReason::Unparseable),
+            ]
+        );
+        assert_eq!(d.present().to_string(), "0-6");
+        assert_eq!(d.online().to_string(), "0-1,3-6");
+    }
+
+    #[test]
+    fn epp() {
+        let fake = FakeSysfs::builder()
+            .epp("balance_performance")
+            .build()
+            .unwrap();
+        let mut c = Core::discover(fake.path("cpu0")).unwrap();
+        assert_eq!(c.epp(), Some("balance_performance"));
+        assert_eq!(c.available_epps().len(), 5);
+
+        assert_eq!(c.validate_epp("power").unwrap(), "power");
+        assert_eq!(c.validate_epp("128").unwrap(), "128");
+        assert!(matches!(
+            c.validate_epp("256"),
+            Err(Error::OutOfRange { max: 255, .. })
+        ));
+        assert!(matches!(
+            c.validate_epp("turbo"),
+            Err(Error::InvalidInput { .. })
+        ));
+
+        c.set_epp("64").unwrap();
+        assert_eq!(Core::discover(fake.path("cpu0")).unwrap().epp(), Some("64"));
+
+        // intel_pstate in passive mode keeps the EPP of HWP
+        let fake = FakeSysfs::builder()
+            .intel_pstate("passive")
+            .epp("balance_performance")
+            .build()
+            .unwrap();
+        let mut c = Core::discover(fake.path("cpu0")).unwrap();
+        assert_eq!(c.driver(), &Driver::IntelCpufreq);
+        assert_eq!(c.validate_epp("128").unwrap(), "128");
+        c.set_epp("128").unwrap();
+        assert_eq!(
+            Core::discover(fake.path("cpu0")).unwrap().epp(),
+            Some("128")
+        );
+
+        // amd-pstate-epp only takes the names
+        let fake = FakeSysfs::builder()
+            .amd_pstate("active")
+            .epp("balance_performance")
+            .build()
+            .unwrap();
+        let c = Core::discover(fake.path("cpu0")).unwrap();
+        assert_eq!(c.validate_epp("power").unwrap(), "power");
+        assert!(matches!(
+            c.validate_epp("128"),
+            Err(Error::InvalidInput { .. })
+        ));
+
+        let fake = FakeSysfs::builder().build().unwrap();
+        let c = Core::discover(fake.path("cpu0")).unwrap();
+        assert!(c.epp().is_none());
+        assert!(matches!(
+            c.validate_epp("power"),
+            Err(Error::NotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn set_speed() {
+        install_emulation();
+        let fake = FakeSysfs::builder()
+            .cores(2)
+            .governors(&["userspace", "ondemand"])
+            .frequencies(&[800_000, 1_600_000, 3_000_000])
+            .build()
+            .unwrap();
+        let mut c = Core::discover(fake.path("cpu1")).unwrap();
+        assert_eq!(c.speed(), Some(Frequency::from_khz(800_000)));
+
+        c.set_speed(Frequency::from_khz(1_600_000)).unwrap();
+        assert_eq!(
+            fake.read("cpu1/cpufreq/scaling_setspeed").unwrap(),
+            "1600000"
+        );
+        assert_eq!(
+            fake.read("cpu1/cpufreq/scaling_cur_freq").unwrap(),
+            "1600000"
+        );
+        assert!(matches!(
+            c.set_speed(Frequency::from_khz(2_000_000)),
+            Err(Error::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            c.set_speed(Frequency::from_khz(3_200_000)),
+            Err(Error::FrequencyOutOfRange { .. })
+        ));
+
+        // only the userspace governor accepts a speed
+        c.set_governor("ondemand").unwrap();
+        let mut c = Core::discover(fake.path("cpu1")).unwrap();
+        assert_eq!(c.speed(), None);
+        assert!(matches!(
+            c.set_speed(Frequency::from_khz(1_600_000)),
+            Err(Error::Rejected { .. })
+        ));
+    }
 }
@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use {read_cpulist, CpuSet, Error, FreqStats, Result, SYSFS_CPU_ROOT};
+
+/// find the cpufreq policies in /sys/devices/system/cpu/cpufreq/policy<x>
+pub fn discover_policies() -> Result<Vec<Policy>> {
+    discover_policies_at(SYSFS_CPU_ROOT)
+}
+
+/// find the cpufreq policies in <root>/cpufreq/policy<x>
+/// Returns an empty list on kernels without policy directories.
+pub fn discover_policies_at<P: AsRef<Path>>(root: P) -> Result<Vec<Policy>> {
+    let dir = root.as_ref().join("cpufreq");
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => match Error::from_io(&dir, e) {
+            Error::NotSupported { .. } => return Ok(vec![]),
+            e => return Err(e),
+        },
+    };
+
+    let mut policies = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with("policy"))
+        .map(|e| Policy::discover(e.path()))
+        .collect::<Result<Vec<_>>>()?;
+    policies.sort_by_key(|p| p.num());
+    debug!("Discovered policies {:#?}", policies);
+
+    Ok(policies)
+}
+
+/// A cpufreq policy. Frequency limits and governor are shared by all cores of a policy,
+/// so changing them on one core changes them on its siblings as well.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Policy {
+    /// Path to the policy directory
+    policy: PathBuf,
+    /// Number of the policy
+    num: u32,
+    /// Online cores governed by the policy
+    affected_cpus: CpuSet,
+    /// All cores governed by the policy, including offline ones
+    related_cpus: CpuSet,
+}
+
+impl Policy {
+    /// discover the policy specified by its path
+    pub fn discover(policy: PathBuf) -> Result<Policy> {
+        let name = policy
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let num = match name.trim_start_matches("policy").parse() {
+            Ok(num) if name.starts_with("policy") => num,
+            _ => {
+                return Err(Error::Parse {
+                    path: policy,
+                    content: name,
+                })
+            }
+        };
+
+        let affected_cpus = read_cpulist(&policy.join("affected_cpus"))?;
+        let related_cpus = read_cpulist(&policy.join("related_cpus"))?;
+
+        Ok(Policy {
+            policy,
+            num,
+            affected_cpus,
+            related_cpus,
+        })
+    }
+
+    /// returns the number of the policy
+    pub fn num(&self) -> u32 {
+        self.num
+    }
+
+    /// returns the online cores governed by the policy
+    pub fn affected_cpus(&self) -> &CpuSet {
+        &self.affected_cpus
+    }
+
+    /// returns all cores governed by the policy, including offline ones
+    pub fn related_cpus(&self) -> &CpuSet {
+        &self.related_cpus
+    }
+
+    /// returns whether the core is governed by this policy
+    pub fn contains(&self, core: u32) -> bool {
+        self.related_cpus.contains(core)
+    }
+
+    /// discover the cpufreq statistics of the policy.
+    /// Fails with [Error::NotSupported] if the driver or kernel does not provide them.
+    pub fn stats(&self) -> Result<FreqStats> {
+        FreqStats::discover(self.policy.join("stats"))
+    }
+
+    /// Given a selection of cores, returns the online cores of this policy which are not selected
+    /// but will be changed anyway since the policy covers some of the selected cores.
+    /// Empty if the selection covers none or all of the policy.
+    pub fn unselected_siblings(&self, selection: &CpuSet) -> CpuSet {
+        if self.affected_cpus.is_disjoint(selection) {
+            return CpuSet::new();
+        }
+        self.affected_cpus.difference(selection)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::discover_policies_at;
+    use fake::FakeSysfs;
+    use {discover_at, discover_core_settings_at, Core, CpuSet, Frequency};
+
+    #[test]
+    fn policies() {
+        let fake = FakeSysfs::builder()
+            .cores(4)
+            .policy(&[0, 1])
+            .policy(&[2, 3])
+            .build()
+            .unwrap();
+
+        let policies = discover_policies_at(fake.root()).unwrap();
+        assert_eq!(policies.len(), 2);
+        assert_eq!(policies[1].num(), 2);
+        assert_eq!(policies[1].related_cpus().to_string(), "2-3");
+        let selection: CpuSet = "1-3".parse().unwrap();
+        assert_eq!(policies[0].unselected_siblings(&selection).to_string(), "0");
+        assert!(policies[1].unselected_siblings(&selection).is_empty());
+
+        // the discovery groups the cores by policy
+        fake.write("cpu3/online", "0").unwrap();
+        let d = discover_at(fake.root()).unwrap();
+        let grouped = d.policies();
+        assert_eq!(grouped.len(), 2);
+        let nums = |i: usize| grouped[i].1.iter().map(|c| c.num()).collect::<Vec<_>>();
+        assert_eq!(nums(0), vec![0, 1]);
+        assert_eq!(nums(1), vec![2]);
+        fake.write("cpu3/online", "1").unwrap();
+
+        let mut cores = discover_core_settings_at(fake.root()).unwrap();
+        cores.sort_by_key(|c| c.num());
+        assert_eq!(cores[3].policy(), Some(2));
+
+        // siblings share their settings
+        cores[3].set_max(Frequency::from_khz(1000000)).unwrap();
+        let c = Core::discover(fake.path("cpu2")).unwrap();
+        assert_eq!(c.curr_max(), Frequency::from_khz(1000000));
+    }
+}
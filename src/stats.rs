@@ -0,0 +1,316 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use {write_attr, Error, Frequency, Result};
+
+/// Unit of time_in_state in milliseconds, the kernel reports clock ticks of USER_HZ (100 per second)
+const TICK_MS: u64 = 10;
+
+/// Read a multi-line sysfs attribute, keeping the line breaks read_attr strips
+fn read_table(path: &Path) -> Result<String> {
+    fs::read_to_string(path).map_err(|e| Error::from_io(path, e))
+}
+
+fn parse_u64(path: &Path, content: &str) -> Result<u64> {
+    content.trim().parse().map_err(|_| Error::Parse {
+        path: path.to_path_buf(),
+        content: content.into(),
+    })
+}
+
+/// Time spent at a frequency as read from one line of stats/time_in_state
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Residency {
+    /// The frequency
+    freq: Frequency,
+    /// Time spent at the frequency since boot or the last reset
+    time: Duration,
+}
+
+impl Residency {
+    /// returns the frequency
+    pub fn freq(&self) -> Frequency {
+        self.freq
+    }
+
+    /// returns the time spent at the frequency
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+}
+
+/// Number of transitions between each pair of frequencies as read from stats/trans_table
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransTable {
+    /// Frequencies in the order the kernel lists them, indexing both rows and columns
+    freqs: Vec<Frequency>,
+    /// counts[from][to]
+    counts: Vec<Vec<u64>>,
+}
+
+impl TransTable {
+    /// Parse the kernel's table: two header lines, then one row per source frequency
+    /// ("3000000:  0  12  4") with a column per target frequency.
+    /// None for an empty or header-only table, e.g. on policies switching frequencies fast.
+    fn parse(path: &Path, content: &str) -> Result<Option<TransTable>> {
+        let err = |line: &str| Error::Parse {
+            path: path.to_path_buf(),
+            content: line.into(),
+        };
+        // splits "head: col col ..." and parses the columns
+        let split = |line: &str| -> Result<(String, Vec<u64>)> {
+            let mut parts = line.splitn(2, ':');
+            let head = parts.next().unwrap_or_default().trim().to_string();
+            let cols = parts
+                .next()
+                .ok_or_else(|| err(line))?
+                .split_whitespace()
+                .map(|c| c.parse().map_err(|_| err(line)))
+                .collect::<Result<Vec<u64>>>()?;
+            Ok((head, cols))
+        };
+
+        // the first line reads "   From  :    To", the second lists the target frequencies
+        let mut lines = content.lines().skip(1).filter(|l| !l.trim().is_empty());
+        let header = match lines.next() {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let freqs = header
+            .split(':')
+            .nth(1)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|f| f.parse().map(Frequency::from_khz).map_err(|_| err(header)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut counts = Vec::with_capacity(freqs.len());
+        for line in lines {
+            let (from, row) = split(line)?;
+            let expected = freqs.get(counts.len()).map(|f| f.as_khz().to_string());
+            if expected.as_deref() != Some(from.as_str()) || row.len() != freqs.len() {
+                return Err(err(line));
+            }
+            counts.push(row);
+        }
+        if counts.is_empty() {
+            return Ok(None);
+        }
+        if counts.len() != freqs.len() {
+            return Err(err(content));
+        }
+
+        Ok(Some(TransTable { freqs, counts }))
+    }
+
+    /// returns the frequencies in the order the kernel lists them
+    pub fn freqs(&self) -> &[Frequency] {
+        self.freqs.as_ref()
+    }
+
+    /// returns the number of transitions from one frequency to another, None if either is not listed
+    pub fn transitions(&self, from: Frequency, to: Frequency) -> Option<u64> {
+        let from = self.freqs.iter().position(|f| *f == from)?;
+        let to = self.freqs.iter().position(|f| *f == to)?;
+        Some(self.counts[from][to])
+    }
+}
+
+/// cpufreq statistics of a policy as read from cpufreq/policy<x>/stats.
+/// Only drivers with a frequency table provide them (not intel_pstate in active mode)
+/// and only on kernels built with CONFIG_CPU_FREQ_STAT.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FreqStats {
+    /// Path to the stats directory
+    dir: PathBuf,
+    /// Time spent at each frequency in the order the kernel lists them
+    time_in_state: Vec<Residency>,
+    /// Number of frequency transitions
+    total_trans: u64,
+    /// Transitions between each pair of frequencies, None if the kernel does not publish the table,
+    /// leaves it empty or fails to read it (with EFBIG if it exceeds a page)
+    trans_table: Option<TransTable>,
+}
+
+impl FreqStats {
+    /// discover the statistics in the given stats directory
+    pub fn discover(dir: PathBuf) -> Result<FreqStats> {
+        let path = dir.join("time_in_state");
+        let time_in_state = read_table(&path)?
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|line| {
+                let mut cols = line.split_whitespace();
+                match (
+                    cols.next().map(|f| f.parse::<u32>()),
+                    cols.next().map(|t| t.parse::<u64>()),
+                ) {
+                    (Some(Ok(freq)), Some(Ok(ticks))) => Ok(Residency {
+                        freq: Frequency::from_khz(freq),
+                        time: Duration::from_millis(ticks.saturating_mul(TICK_MS)),
+                    }),
+                    _ => Err(Error::Parse {
+                        path: path.clone(),
+                        content: line.into(),
+                    }),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let path = dir.join("total_trans");
+        let total_trans = parse_u64(&path, &read_table(&path)?)?;
+
+        let path = dir.join("trans_table");
+        let trans_table = match read_table(&path) {
+            Ok(table) => TransTable::parse(&path, &table)?,
+            Err(Error::NotSupported { .. }) | Err(Error::PermissionDenied { .. }) => None,
+            Err(e @ Error::Io { .. }) => {
+                debug!("Leaving out the transition table: {}", e);
+                None
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok(FreqStats {
+            dir,
+            time_in_state,
+            total_trans,
+            trans_table,
+        })
+    }
+
+    /// returns the time spent at each frequency
+    pub fn time_in_state(&self) -> &[Residency] {
+        self.time_in_state.as_ref()
+    }
+
+    /// returns the total time covered by [FreqStats::time_in_state]
+    pub fn total_time(&self) -> Duration {
+        self.time_in_state.iter().map(|r| r.time).sum()
+    }
+
+    /// returns the number of frequency transitions
+    pub fn total_trans(&self) -> u64 {
+        self.total_trans
+    }
+
+    /// returns the transitions between each pair of frequencies
+    pub fn trans_table(&self) -> Option<&TransTable> {
+        self.trans_table.as_ref()
+    }
+
+    /// Reset all statistics to zero. Discover them again to see the result.
+    pub fn reset(&mut self) -> Result<()> {
+        debug!("Resetting cpufreq statistics in {:?}", self.dir);
+        write_attr(&self.dir.join("reset"), "1")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TransTable;
+    use fake::{install_emulation, FakeSysfs};
+    use std::fs;
+    use std::path::Path;
+    use std::time::Duration;
+    use {discover_policies_at, Core, Error, Frequency};
+
+    #[test]
+    fn trans_table() {
+        let content = "   From  :    To\n         :   2000000   1000000 \n  2000000:         0         3 \n  1000000:         4         0 \n";
+        let table = TransTable::parse(Path::new("trans_table"), content)
+            .unwrap()
+            .unwrap();
+        let (hi, lo) = (
+            Frequency::from_khz(2_000_000),
+            Frequency::from_khz(1_000_000),
+        );
+        assert_eq!(table.freqs(), &[hi, lo]);
+        assert_eq!(table.transitions(hi, lo), Some(3));
+        assert_eq!(table.transitions(lo, hi), Some(4));
+        assert_eq!(table.transitions(lo, Frequency::from_khz(3)), None);
+
+        let truncated =
+            "   From  :    To\n         :   2000000   1000000 \n  2000000:         0         3 \n";
+        assert!(TransTable::parse(Path::new("trans_table"), truncated).is_err());
+
+        let header = "   From  :    To\n         :   2000000   1000000 \n";
+        assert!(TransTable::parse(Path::new("trans_table"), header)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn stats() {
+        install_emulation();
+        let fake = FakeSysfs::builder()
+            .cores(2)
+            .policy(&[0, 1])
+            .governors(&["userspace", "schedutil"])
+            .frequencies(&[800_000, 1_600_000, 3_000_000])
+            .stats()
+            .build()
+            .unwrap();
+        fake.write(
+            "cpufreq/policy0/stats/time_in_state",
+            "3000000 100\n1600000 0\n800000 300",
+        )
+        .unwrap();
+        let policies = discover_policies_at(fake.root()).unwrap();
+        let stats = policies[0].stats().unwrap();
+        assert_eq!(stats.time_in_state().len(), 3);
+        assert_eq!(stats.time_in_state()[0].time(), Duration::from_secs(1));
+        assert_eq!(stats.total_time(), Duration::from_secs(4));
+        assert_eq!(stats.total_trans(), 0);
+
+        let mut c = Core::discover(fake.path("cpu0")).unwrap();
+        c.set_speed(Frequency::from_khz(3_000_000)).unwrap();
+        let mut stats = policies[0].stats().unwrap();
+        assert_eq!(stats.total_trans(), 1);
+        assert_eq!(
+            stats
+                .trans_table()
+                .unwrap()
+                .transitions(Frequency::from_khz(800_000), Frequency::from_khz(3_000_000)),
+            Some(1)
+        );
+
+        stats.reset().unwrap();
+        let stats = policies[0].stats().unwrap();
+        assert_eq!(stats.total_trans(), 0);
+        assert_eq!(stats.total_time(), Duration::from_secs(0));
+
+        let fake = FakeSysfs::builder().build().unwrap();
+        let policies = discover_policies_at(fake.root()).unwrap();
+        assert!(matches!(
+            policies[0].stats(),
+            Err(Error::NotSupported { .. })
+        ));
+    }
+
+    #[test]
+    fn unreadable_trans_table() {
+        let fake = FakeSysfs::builder().cores(1).stats().build().unwrap();
+        // reading fails like the kernel's EFBIG for tables exceeding a page
+        fake.remove("cpufreq/policy0/stats/trans_table").unwrap();
+        fs::create_dir(fake.path("cpufreq/policy0/stats/trans_table")).unwrap();
+
+        let policies = discover_policies_at(fake.root()).unwrap();
+        let stats = policies[0].stats().unwrap();
+        assert!(stats.trans_table().is_none());
+        assert_eq!(stats.time_in_state().len(), 2);
+    }
+
+    #[test]
+    fn empty_trans_table() {
+        let fake = FakeSysfs::builder().cores(1).stats().build().unwrap();
+        // the kernel publishes no table for policies switching frequencies fast
+        fake.write("cpufreq/policy0/stats/trans_table", "").unwrap();
+
+        let policies = discover_policies_at(fake.root()).unwrap();
+        let stats = policies[0].stats().unwrap();
+        assert!(stats.trans_table().is_none());
+        assert_eq!(stats.total_trans(), 0);
+    }
+}
@@ -0,0 +1,306 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use {optional, read_attr, read_num, Error, Result};
+
+/// Default location of the thermal zones and cooling devices in sysfs
+pub const SYSFS_THERMAL_ROOT: &str = "/sys/class/thermal";
+
+/// find the thermal zones and cooling devices in /sys/class/thermal
+pub fn discover_thermal() -> Result<Thermal> {
+    discover_thermal_at(SYSFS_THERMAL_ROOT)
+}
+
+/// find the thermal zones and cooling devices in the given directory, which replaces /sys/class/thermal.
+/// Unlike the cpu root it lies outside of /sys/devices/system/cpu, so it has to be given separately.
+/// Both lists are empty on systems without thermal support.
+pub fn discover_thermal_at<P: AsRef<Path>>(dir: P) -> Result<Thermal> {
+    let dir = dir.as_ref();
+
+    let mut zones = Vec::new();
+    let mut cooling_devices = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => match Error::from_io(dir, e) {
+            Error::NotSupported { .. } => return Ok(Thermal::default()),
+            e => return Err(e),
+        },
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if let Some(num) = name
+            .strip_prefix("thermal_zone")
+            .and_then(|n| n.parse().ok())
+        {
+            zones.push(ThermalZone::discover(entry.path(), num)?);
+        } else if let Some(num) = name
+            .strip_prefix("cooling_device")
+            .and_then(|n| n.parse().ok())
+        {
+            cooling_devices.push(CoolingDevice::discover(entry.path(), num)?);
+        }
+    }
+    zones.sort_by_key(|z| z.num);
+    cooling_devices.sort_by_key(|c| c.num);
+
+    let t = Thermal {
+        zones,
+        cooling_devices,
+    };
+    debug!("Discovered thermal {:#?}", t);
+    Ok(t)
+}
+
+/// Read a temperature in millidegrees Celsius, which may be negative.
+/// None if the sensor cannot be read right now, e.g. because its device is powered down.
+fn read_temp(path: &Path) -> Result<Option<i32>> {
+    let content = match optional(read_attr(path)) {
+        Ok(Some(content)) => content,
+        Ok(None) | Err(Error::Io { .. }) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    content.trim().parse().map(Some).map_err(|_| Error::Parse {
+        path: path.to_path_buf(),
+        content,
+    })
+}
+
+/// Read a cooling state. None if the device cannot be read right now (e.g. an ACPI fan failing with EIO)
+/// or reports no valid state, like intel_powerclamp's -1.
+fn read_state(path: &Path) -> Result<Option<u32>> {
+    match optional(read_num(path)) {
+        Err(e @ Error::Io { .. }) | Err(e @ Error::Parse { .. }) => {
+            debug!("Ignoring cooling state: {}", e);
+            Ok(None)
+        }
+        res => res,
+    }
+}
+
+/// Thermal zones and cooling devices, e.g. to tell whether cores run below their limits
+/// because of throttling
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Thermal {
+    /// Thermal zones ordered by number
+    zones: Vec<ThermalZone>,
+    /// Cooling devices ordered by number
+    cooling_devices: Vec<CoolingDevice>,
+}
+
+impl Thermal {
+    /// returns the thermal zones
+    pub fn zones(&self) -> &[ThermalZone] {
+        self.zones.as_ref()
+    }
+
+    /// returns the cooling devices
+    pub fn cooling_devices(&self) -> &[CoolingDevice] {
+        self.cooling_devices.as_ref()
+    }
+
+    /// returns whether any cooling device is active, e.g. a processor being throttled
+    pub fn is_cooling(&self) -> bool {
+        self.cooling_devices
+            .iter()
+            .any(|c| c.cur_state.is_some_and(|s| s > 0))
+    }
+}
+
+/// A thermal zone as read from class/thermal/thermal_zone<x>
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThermalZone {
+    /// Path to the zone directory
+    dir: PathBuf,
+    /// Number of the zone
+    num: u32,
+    /// What the zone measures, e.g. x86_pkg_temp or acpitz
+    #[serde(rename = "type")]
+    kind: String,
+    /// Temperature in millidegrees Celsius, None if the sensor cannot be read
+    temp: Option<i32>,
+    /// Trip points ordered by number
+    trip_points: Vec<TripPoint>,
+}
+
+impl ThermalZone {
+    /// discover the zone in the given thermal_zone<x> directory
+    pub fn discover(dir: PathBuf, num: u32) -> Result<ThermalZone> {
+        let kind = read_attr(&dir.join("type"))?;
+        let temp = read_temp(&dir.join("temp"))?;
+
+        let mut trip_points = Vec::new();
+        for k in 0.. {
+            let kind = match optional(read_attr(&dir.join(format!("trip_point_{}_type", k))))? {
+                Some(kind) => kind,
+                None => break,
+            };
+            let temp = read_temp(&dir.join(format!("trip_point_{}_temp", k)))?;
+            let hyst = read_temp(&dir.join(format!("trip_point_{}_hyst", k)))?;
+            trip_points.push(TripPoint { kind, temp, hyst });
+        }
+
+        Ok(ThermalZone {
+            dir,
+            num,
+            kind,
+            temp,
+            trip_points,
+        })
+    }
+
+    /// returns the number of the zone
+    pub fn num(&self) -> u32 {
+        self.num
+    }
+
+    /// returns what the zone measures, e.g. x86_pkg_temp
+    pub fn kind(&self) -> &str {
+        self.kind.as_ref()
+    }
+
+    /// returns the temperature in millidegrees Celsius
+    pub fn temp(&self) -> Option<i32> {
+        self.temp
+    }
+
+    /// returns the trip points
+    pub fn trip_points(&self) -> &[TripPoint] {
+        self.trip_points.as_ref()
+    }
+}
+
+/// A temperature at which a thermal zone acts, as read from trip_point_<k>_*
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TripPoint {
+    /// What happens at the trip point: active (fans), passive (throttling), hot or critical (shutdown)
+    #[serde(rename = "type")]
+    kind: String,
+    /// Temperature in millidegrees Celsius
+    temp: Option<i32>,
+    /// Hysteresis in millidegrees Celsius, None if the driver does not provide it
+    hyst: Option<i32>,
+}
+
+impl TripPoint {
+    /// returns what happens at the trip point, e.g. passive or critical
+    pub fn kind(&self) -> &str {
+        self.kind.as_ref()
+    }
+
+    /// returns the temperature in millidegrees Celsius
+    pub fn temp(&self) -> Option<i32> {
+        self.temp
+    }
+
+    /// returns the hysteresis in millidegrees Celsius
+    pub fn hyst(&self) -> Option<i32> {
+        self.hyst
+    }
+}
+
+/// A cooling device as read from class/thermal/cooling_device<x>, e.g. a fan or processor throttling
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoolingDevice {
+    /// Path to the device directory
+    dir: PathBuf,
+    /// Number of the device
+    num: u32,
+    /// What the device is, e.g. Processor, Fan or intel_powerclamp
+    #[serde(rename = "type")]
+    kind: String,
+    /// Current cooling state, 0 means not cooling; None if unreadable
+    cur_state: Option<u32>,
+    /// Highest cooling state, None if unreadable
+    max_state: Option<u32>,
+}
+
+impl CoolingDevice {
+    /// discover the device in the given cooling_device<x> directory
+    pub fn discover(dir: PathBuf, num: u32) -> Result<CoolingDevice> {
+        let kind = read_attr(&dir.join("type"))?;
+        let cur_state = read_state(&dir.join("cur_state"))?;
+        let max_state = read_state(&dir.join("max_state"))?;
+
+        Ok(CoolingDevice {
+            dir,
+            num,
+            kind,
+            cur_state,
+            max_state,
+        })
+    }
+
+    /// returns the number of the device
+    pub fn num(&self) -> u32 {
+        self.num
+    }
+
+    /// returns what the device is, e.g. Processor or Fan
+    pub fn kind(&self) -> &str {
+        self.kind.as_ref()
+    }
+
+    /// returns the current cooling state, 0 means not cooling
+    pub fn cur_state(&self) -> Option<u32> {
+        self.cur_state
+    }
+
+    /// returns the highest cooling state
+    pub fn max_state(&self) -> Option<u32> {
+        self.max_state
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::discover_thermal_at;
+    use fake::FakeSysfs;
+    use std::fs;
+
+    #[test]
+    fn thermal() {
+        let fake = FakeSysfs::builder()
+            .thermal_zone("x86_pkg_temp", 62_500)
+            .thermal_zone("acpitz", -5_000)
+            .cooling_device("Processor", 3, 10)
+            .cooling_device("intel_powerclamp", 0, 50)
+            .cooling_device("Fan", 0, 1)
+            .build()
+            .unwrap();
+        let dev =
+            |n: u32, attr: &str| format!("../../../class/thermal/cooling_device{}/{}", n, attr);
+        fake.write(dev(1, "max_state"), "-1").unwrap();
+        fake.write(dev(1, "cur_state"), "18446744073709551615")
+            .unwrap();
+        // ACPI fans fail with EIO at times
+        fake.remove(dev(2, "cur_state")).unwrap();
+        fs::create_dir(fake.path(dev(2, "cur_state"))).unwrap();
+
+        let t = discover_thermal_at(fake.thermal_root()).unwrap();
+        let kinds = t.zones().iter().map(|z| z.kind()).collect::<Vec<_>>();
+        assert_eq!(kinds, vec!["x86_pkg_temp", "acpitz"]);
+        assert_eq!(t.zones()[0].temp(), Some(62_500));
+        assert_eq!(t.zones()[1].temp(), Some(-5_000));
+        let trips = t.zones()[0].trip_points();
+        assert_eq!(trips.len(), 2);
+        assert_eq!(
+            (trips[1].kind(), trips[1].temp()),
+            ("critical", Some(105_000))
+        );
+        let states = t
+            .cooling_devices()
+            .iter()
+            .map(|c| (c.cur_state(), c.max_state()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            states,
+            vec![(Some(3), Some(10)), (None, None), (None, Some(1))]
+        );
+        assert!(t.is_cooling());
+
+        let fake = FakeSysfs::builder().build().unwrap();
+        let t = discover_thermal_at(fake.thermal_root()).unwrap();
+        assert!(t.zones().is_empty() && t.cooling_devices().is_empty());
+        assert!(!t.is_cooling());
+    }
+}
@@ -0,0 +1,125 @@
+use std::fs;
+use std::path::Path;
+
+use {optional, read_attr, read_cpulist, CpuSet, Error, Result};
+
+/// Read a sysfs attribute containing a single signed number; topology ids are -1 where unknown
+fn read_id(path: &Path) -> Result<i32> {
+    let content = read_attr(path)?;
+    content.trim().parse().map_err(|_| Error::Parse {
+        path: path.to_path_buf(),
+        content,
+    })
+}
+
+/// Placement of a core in the cpu topology as read from cpu<x>/topology and the cpu<x>/node<y> link.
+/// Ids are -1 where the platform does not provide them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Topology {
+    /// Physical package (socket) id
+    physical_package_id: i32,
+    /// Die id within the package, not provided by older kernels
+    die_id: Option<i32>,
+    /// Cluster id (cores sharing e.g. an L2 cache), not provided by older kernels
+    cluster_id: Option<i32>,
+    /// Physical core id within the package
+    core_id: i32,
+    /// Logical cores sharing the physical core (SMT siblings), including this one
+    thread_siblings: CpuSet,
+    /// NUMA node, None on kernels without NUMA support
+    node: Option<u32>,
+}
+
+impl Topology {
+    /// discover the topology of the core specified by its path.
+    /// Returns None if the core has no topology directory.
+    pub fn discover(core: &Path) -> Result<Option<Topology>> {
+        let t = core.join("topology");
+        if !t.is_dir() {
+            return Ok(None);
+        }
+
+        let physical_package_id = read_id(&t.join("physical_package_id"))?;
+        let die_id = optional(read_id(&t.join("die_id")))?;
+        let cluster_id = optional(read_id(&t.join("cluster_id")))?;
+        let core_id = read_id(&t.join("core_id"))?;
+        let thread_siblings = read_cpulist(&t.join("thread_siblings_list"))?;
+
+        // the core directory links to its node as node<y>
+        let node = fs::read_dir(core)
+            .map_err(|e| Error::from_io(core, e))?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                name.strip_prefix("node").and_then(|n| n.parse().ok())
+            })
+            .next();
+
+        Ok(Some(Topology {
+            physical_package_id,
+            die_id,
+            cluster_id,
+            core_id,
+            thread_siblings,
+            node,
+        }))
+    }
+
+    /// returns the physical package (socket) id
+    pub fn package(&self) -> i32 {
+        self.physical_package_id
+    }
+
+    /// returns the die id within the package
+    pub fn die(&self) -> Option<i32> {
+        self.die_id
+    }
+
+    /// returns the cluster id
+    pub fn cluster(&self) -> Option<i32> {
+        self.cluster_id
+    }
+
+    /// returns the physical core id within the package
+    pub fn core(&self) -> i32 {
+        self.core_id
+    }
+
+    /// returns the logical cores sharing the physical core, including this one
+    pub fn thread_siblings(&self) -> &CpuSet {
+        &self.thread_siblings
+    }
+
+    /// returns the NUMA node
+    pub fn node(&self) -> Option<u32> {
+        self.node
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use fake::FakeSysfs;
+    use {discover_core_settings_at, Core};
+
+    #[test]
+    fn topology() {
+        let fake = FakeSysfs::builder()
+            .cores(8)
+            .packages(2)
+            .threads_per_core(2)
+            .build()
+            .unwrap();
+
+        let cores = discover_core_settings_at(fake.root()).unwrap();
+        let t = cores[5].topology().unwrap();
+        assert_eq!(t.package(), 1);
+        assert_eq!(t.core(), 0);
+        assert_eq!(t.thread_siblings().to_string(), "4-5");
+        assert_eq!(t.node(), Some(1));
+        assert_eq!(t.die(), Some(0));
+
+        fake.remove("cpu1/topology").unwrap();
+        let c = Core::discover(fake.path("cpu1")).unwrap();
+        assert!(c.topology().is_none());
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use {read_num, write_attr, Error, Result, SYSFS_CPU_ROOT};
+
+/// Valid ranges of the known tunables as checked by the kernel
+const RANGES: &[(&str, &str, u32, u32)] = &[
+    ("ondemand", "up_threshold", 1, 100),
+    ("ondemand", "sampling_rate", 1, u32::MAX),
+    ("ondemand", "sampling_down_factor", 1, 100_000),
+    ("ondemand", "io_is_busy", 0, 1),
+    ("ondemand", "ignore_nice_load", 0, 1),
+    ("ondemand", "powersave_bias", 0, 1000),
+    ("conservative", "up_threshold", 1, 100),
+    ("conservative", "down_threshold", 1, 99),
+    ("conservative", "sampling_rate", 1, u32::MAX),
+    ("conservative", "sampling_down_factor", 1, 10),
+    ("conservative", "ignore_nice_load", 0, 1),
+    ("conservative", "freq_step", 0, 100),
+    ("schedutil", "rate_limit_us", 0, u32::MAX),
+];
+
+/// find the tunables of the governor in /sys/devices/system/cpu/cpufreq
+pub fn discover_tunables(governor: &str) -> Result<Vec<GovernorTunables>> {
+    discover_tunables_at(SYSFS_CPU_ROOT, governor)
+}
+
+/// find the tunables of the governor in <root>/cpufreq.
+/// Depending on the driver they are either global (cpufreq/<governor>) or per policy
+/// (cpufreq/policy<x>/<governor>). The kernel only publishes them while the governor is in use,
+/// so the result is empty otherwise.
+pub fn discover_tunables_at<P: AsRef<Path>>(
+    root: P,
+    governor: &str,
+) -> Result<Vec<GovernorTunables>> {
+    let cpufreq = root.as_ref().join("cpufreq");
+    let mut found = Vec::new();
+
+    let global = cpufreq.join(governor);
+    if global.is_dir() {
+        found.push(GovernorTunables::discover(governor, global, None)?);
+    }
+
+    if let Ok(entries) = fs::read_dir(&cpufreq) {
+        let mut policies = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                name.strip_prefix("policy")
+                    .and_then(|n| n.parse::<u32>().ok())
+                    .map(|n| (n, e.path().join(governor)))
+            })
+            .filter(|(_, dir)| dir.is_dir())
+            .collect::<Vec<_>>();
+        policies.sort();
+        for (num, dir) in policies {
+            found.push(GovernorTunables::discover(governor, dir, Some(num))?);
+        }
+    }
+    debug!("Discovered tunables {:#?}", found);
+
+    Ok(found)
+}
+
+/// The tunables of a governor in one location, e.g. up_threshold of ondemand.
+/// All known tunables are numbers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GovernorTunables {
+    /// Name of the governor
+    governor: String,
+    /// Path to the tunables directory
+    dir: PathBuf,
+    /// Policy the tunables apply to, None if they apply to all policies
+    policy: Option<u32>,
+    /// Tunables by name
+    tunables: BTreeMap<String, u32>,
+}
+
+impl GovernorTunables {
+    /// discover the tunables in the given directory. Attributes which are not numbers are left out.
+    pub fn discover(governor: &str, dir: PathBuf, policy: Option<u32>) -> Result<GovernorTunables> {
+        let mut tunables = BTreeMap::new();
+        for entry in fs::read_dir(&dir)
+            .map_err(|e| Error::from_io(&dir, e))?
+            .filter_map(|e| e.ok())
+        {
+            match read_num(&entry.path()) {
+                Ok(value) => {
+                    tunables.insert(entry.file_name().to_string_lossy().into_owned(), value);
+                }
+                Err(Error::Parse { .. }) | Err(Error::PermissionDenied { .. }) => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(GovernorTunables {
+            governor: governor.into(),
+            dir,
+            policy,
+            tunables,
+        })
+    }
+
+    /// returns the name of the governor
+    pub fn governor(&self) -> &str {
+        &self.governor
+    }
+
+    /// returns the policy the tunables apply to, None if they apply to all policies
+    pub fn policy(&self) -> Option<u32> {
+        self.policy
+    }
+
+    /// returns all tunables by name
+    pub fn tunables(&self) -> &BTreeMap<String, u32> {
+        &self.tunables
+    }
+
+    /// returns the value of a tunable
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.tunables.get(name).cloned()
+    }
+
+    /// Validate a tunable: it must exist and, for the well-known ones, be in the range the kernel accepts
+    pub fn validate(&self, name: &str, value: u32) -> Result<u32> {
+        if !self.tunables.contains_key(name) {
+            return Err(Error::InvalidInput {
+                input: name.into(),
+                expected: format!(
+                    "a tunable of {}: {}",
+                    self.governor,
+                    self.tunables
+                        .keys()
+                        .map(|k| k.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            });
+        }
+
+        match range(&self.governor, name) {
+            Some((min, max)) if value < min || value > max => {
+                Err(Error::OutOfRange { value, min, max })
+            }
+            _ => Ok(value),
+        }
+    }
+
+    /// Set a tunable.
+    /// This operation is not checked by mediocore, but the kernel may refuse to accept certain inputs.
+    /// Use [GovernorTunables::validate] on the value beforehand.
+    pub fn set(&mut self, name: &str, value: u32) -> Result<()> {
+        debug!(
+            "Setting {} {} to {} in {:?}",
+            self.governor, name, value, self.dir
+        );
+        write_attr(&self.dir.join(name), &value.to_string())
+    }
+}
+
+/// returns the range the kernel accepts for a tunable of the governor, None if unknown
+pub(crate) fn range(governor: &str, name: &str) -> Option<(u32, u32)> {
+    RANGES
+        .iter()
+        .find(|r| r.0 == governor && r.1 == name)
+        .map(|r| (r.2, r.3))
+}
+
+#[cfg(test)]
+mod test {
+    use super::discover_tunables_at;
+    use fake::{install_emulation, FakeSysfs};
+    use {Core, Error};
+
+    #[test]
+    fn tunables() {
+        install_emulation();
+        let fake = FakeSysfs::builder()
+            .cores(2)
+            .governors(&["ondemand", "schedutil", "performance"])
+            .build()
+            .unwrap();
+        let mut found = discover_tunables_at(fake.root(), "ondemand").unwrap();
+        assert_eq!(found.len(), 1);
+        let t = &mut found[0];
+        assert_eq!(t.policy(), None);
+        assert_eq!(t.get("up_threshold"), Some(80));
+        assert!(t.validate("up_threshold", 95).is_ok());
+        assert!(matches!(
+            t.validate("up_threshold", 101),
+            Err(Error::OutOfRange { max: 100, .. })
+        ));
+        assert!(matches!(
+            t.validate("rate_limit_us", 100),
+            Err(Error::InvalidInput { .. })
+        ));
+        t.set("up_threshold", 95).unwrap();
+        assert_eq!(fake.read("cpufreq/ondemand/up_threshold").unwrap(), "95");
+        assert!(matches!(
+            t.set("io_is_busy", 2),
+            Err(Error::Rejected { .. })
+        ));
+
+        // only published while in use
+        assert!(discover_tunables_at(fake.root(), "schedutil")
+            .unwrap()
+            .is_empty());
+        for c in 0..2 {
+            let mut core = Core::discover(fake.path(format!("cpu{}", c))).unwrap();
+            core.set_governor("schedutil").unwrap();
+        }
+        assert!(discover_tunables_at(fake.root(), "ondemand")
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            discover_tunables_at(fake.root(), "schedutil")
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn tunables_per_policy() {
+        install_emulation();
+        let fake = FakeSysfs::builder()
+            .cores(4)
+            .policy(&[0, 1])
+            .governors(&["schedutil", "performance"])
+            .governor_per_policy(true)
+            .build()
+            .unwrap();
+        let found = discover_tunables_at(fake.root(), "schedutil").unwrap();
+        assert_eq!(
+            found.iter().map(|t| t.policy()).collect::<Vec<_>>(),
+            vec![Some(0), Some(2), Some(3)]
+        );
+        assert_eq!(found[1].get("rate_limit_us"), Some(1000));
+        assert!(!fake.path("cpufreq/schedutil").exists());
+
+        let mut core = Core::discover(fake.path("cpu3")).unwrap();
+        core.set_governor("performance").unwrap();
+        assert!(!fake.path("cpufreq/policy3/schedutil").exists());
+        assert_eq!(
+            discover_tunables_at(fake.root(), "schedutil")
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+}
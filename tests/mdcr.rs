@@ -0,0 +1,661 @@
+//! Runs mdcr against a fake sysfs tree. Requires the fake-sysfs feature:
+//! cargo test --features fake-sysfs
+#![cfg(feature = "fake-sysfs")]
+
+extern crate mediocore;
+
+use mediocore::fake::FakeSysfs;
+use std::process::{Command, Output};
+
+fn mdcr(fake: &FakeSysfs, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_mdcr-emulated"))
+        .arg("--sysfs-root")
+        .arg(fake.root())
+        .args(args)
+        .output()
+        .expect("Failed to run mdcr")
+}
+
+#[test]
+fn show_json() {
+    let fake = FakeSysfs::builder()
+        .cores(4)
+        .threads_per_core(2)
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["show", "--json"]);
+    assert!(out.status.success());
+    let json = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(json.matches("\"num\"").count(), 4);
+    // core lists use the kernel's cpulist format
+    assert!(json.contains("\"thread_siblings\": \"2-3\""));
+}
+
+#[test]
+fn set_selected_cores() {
+    let fake = FakeSysfs::builder().cores(2).build().unwrap();
+    let out = mdcr(
+        &fake,
+        &["set", "-c", "1", "--high", "1.5GHz", "-g", "performance"],
+    );
+    assert!(out.status.success());
+    assert_eq!(
+        fake.read("cpu1/cpufreq/scaling_max_freq").unwrap(),
+        "1500000"
+    );
+    assert_eq!(
+        fake.read("cpu1/cpufreq/scaling_governor").unwrap(),
+        "performance"
+    );
+    assert_eq!(
+        fake.read("cpu0/cpufreq/scaling_max_freq").unwrap(),
+        "3000000"
+    );
+}
+
+#[test]
+fn set_rejected_by_kernel() {
+    let fake = FakeSysfs::builder().cores(1).build().unwrap();
+    let out = mdcr(&fake, &["set", "-g", "ondemand"]);
+    assert_eq!(out.status.code(), Some(22));
+}
+
+#[test]
+fn powersave() {
+    let fake = FakeSysfs::builder().cores(2).build().unwrap();
+    let out = mdcr(&fake, &["powersave"]);
+    assert!(out.status.success());
+    for c in 0..2 {
+        assert_eq!(
+            fake.read(format!("cpu{}/cpufreq/scaling_max_freq", c))
+                .unwrap(),
+            "800000"
+        );
+    }
+}
+
+#[test]
+fn set_snapped() {
+    let fake = FakeSysfs::builder()
+        .cores(1)
+        .cpuinfo(800_000, 2_400_000)
+        .frequencies(&[800_000, 1_600_000, 2_400_000])
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["set", "--high", "2.1GHz", "--snap", "nearest"]);
+    assert!(out.status.success());
+    assert_eq!(
+        fake.read("cpu0/cpufreq/scaling_max_freq").unwrap(),
+        "2400000"
+    );
+
+    // without --snap the value is written as given, but mdcr warns about the rounding
+    let out = mdcr(&fake, &["set", "--high", "2GHz"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8(out.stderr).unwrap().contains("1.6GHz"));
+    assert_eq!(
+        fake.read("cpu0/cpufreq/scaling_max_freq").unwrap(),
+        "2000000"
+    );
+}
+
+#[test]
+fn set_partial_policy() {
+    let fake = FakeSysfs::builder()
+        .cores(2)
+        .policy(&[0, 1])
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["show"]);
+    assert!(String::from_utf8(out.stdout)
+        .unwrap()
+        .contains("* Policy 0               cores 0-1"));
+
+    let out = mdcr(&fake, &["set", "-c", "1", "--high", "1GHz"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        fake.read("cpu0/cpufreq/scaling_max_freq").unwrap(),
+        "3000000"
+    );
+
+    let out = mdcr(&fake, &["set", "-c", "1", "--high", "1GHz", "--force"]);
+    assert!(out.status.success());
+    assert_eq!(
+        fake.read("cpu0/cpufreq/scaling_max_freq").unwrap(),
+        "1000000"
+    );
+}
+
+#[test]
+fn show_without_cpufreq() {
+    // like a VM without cpufreq
+    let fake = FakeSysfs::builder().cores(2).build().unwrap();
+    fake.remove("cpu0/cpufreq").unwrap();
+    fake.remove("cpu1/cpufreq").unwrap();
+    let out = mdcr(&fake, &["show"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("No cpufreq-capable cores"));
+    assert!(stdout.contains("cpu1: no cpufreq support"));
+
+    // descriptions too long for a single core per line
+    let long = "g".repeat(80);
+    let fake = FakeSysfs::builder()
+        .cores(2)
+        .governors(&[&long])
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["show"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8(out.stdout).unwrap().contains(&long));
+}
+
+#[test]
+fn hotplug() {
+    let fake = FakeSysfs::builder().cores(4).build().unwrap();
+    let out = mdcr(&fake, &["offline", "-c", "2-3"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8(out.stdout).unwrap().contains("offline"));
+    assert_eq!(fake.read("online").unwrap(), "0-1");
+
+    // cpu0 has no online file
+    let out = mdcr(&fake, &["offline", "-c", "0"]);
+    assert_eq!(out.status.code(), Some(1));
+
+    let out = mdcr(&fake, &["online", "-c", "2"]);
+    assert!(out.status.success());
+    assert_eq!(fake.read("online").unwrap(), "0-2");
+    assert_eq!(fake.read("offline").unwrap(), "3");
+
+    // kernels without the online list
+    let fake = FakeSysfs::builder().cores(3).offline(&[0]).build().unwrap();
+    fake.remove("online").unwrap();
+    let out = mdcr(&fake, &["offline", "-c", "1"]);
+    assert!(out.status.success());
+    assert_eq!(fake.read("cpu1/online").unwrap(), "0");
+    let out = mdcr(&fake, &["offline", "-c", "2"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8(out.stderr)
+        .unwrap()
+        .contains("last online"));
+    assert_eq!(fake.read("cpu2/online").unwrap(), "1");
+}
+
+#[test]
+fn set_by_topology() {
+    let fake = FakeSysfs::builder()
+        .cores(8)
+        .packages(2)
+        .threads_per_core(2)
+        .build()
+        .unwrap();
+    let out = mdcr(
+        &fake,
+        &[
+            "set",
+            "--package",
+            "1",
+            "--physical-core",
+            "1",
+            "--high",
+            "1GHz",
+        ],
+    );
+    assert!(out.status.success());
+    for c in 0..8 {
+        let expected = if c == 6 || c == 7 {
+            "1000000"
+        } else {
+            "3000000"
+        };
+        assert_eq!(
+            fake.read(format!("cpu{}/cpufreq/scaling_max_freq", c))
+                .unwrap(),
+            expected
+        );
+    }
+
+    let out = mdcr(&fake, &["show", "--by-package"]);
+    let table = String::from_utf8(out.stdout).unwrap();
+    assert!(table.contains("Package 0:") && table.contains("Package 1:"));
+}
+
+#[test]
+fn hybrid() {
+    let fake = FakeSysfs::builder()
+        .cores(4)
+        .efficiency(&[2, 3])
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["show"]);
+    assert!(out.status.success());
+    let table = String::from_utf8(out.stdout).unwrap();
+    let p = table.find("Performance Cores:").unwrap();
+    assert!(table.find("Efficiency Cores:").unwrap() > p);
+
+    let out = mdcr(&fake, &["set", "--kind", "efficiency", "--high", "1GHz"]);
+    assert!(out.status.success());
+    assert_eq!(
+        fake.read("cpu2/cpufreq/scaling_max_freq").unwrap(),
+        "1000000"
+    );
+    assert_eq!(
+        fake.read("cpu1/cpufreq/scaling_max_freq").unwrap(),
+        "3000000"
+    );
+}
+
+#[test]
+fn set_by_tier() {
+    let fake = FakeSysfs::builder()
+        .cores(4)
+        .core_capacity(0, 512)
+        .core_capacity(1, 512)
+        .core_capacity(2, 1024)
+        .core_capacity(3, 1024)
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["set", "--tier", "big", "--high", "2GHz"]);
+    assert!(out.status.success());
+    assert_eq!(
+        fake.read("cpu3/cpufreq/scaling_max_freq").unwrap(),
+        "2000000"
+    );
+    assert_eq!(
+        fake.read("cpu1/cpufreq/scaling_max_freq").unwrap(),
+        "3000000"
+    );
+}
+
+#[test]
+fn boost() {
+    let fake = FakeSysfs::builder().boost(true).build().unwrap();
+    let out = mdcr(&fake, &["boost", "off"]);
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "Boost: off\n");
+    assert_eq!(fake.read("intel_pstate/no_turbo").unwrap(), "1");
+
+    let out = mdcr(&fake, &["boost", "status"]);
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "Boost: off\n");
+
+    let fake = FakeSysfs::builder().build().unwrap();
+    let out = mdcr(&fake, &["boost", "on"]);
+    assert_eq!(out.status.code(), Some(95));
+}
+
+#[test]
+fn intel_pstate() {
+    let fake = FakeSysfs::builder()
+        .intel_pstate("active")
+        .epp("balance_performance")
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["show"]);
+    assert!(String::from_utf8(out.stdout)
+        .unwrap()
+        .contains("intel_pstate           active, performance 25-100%"));
+
+    let out = mdcr(
+        &fake,
+        &[
+            "intel-pstate",
+            "--status",
+            "passive",
+            "--max-perf-pct",
+            "80",
+        ],
+    );
+    assert!(out.status.success());
+    assert_eq!(fake.read("intel_pstate/status").unwrap(), "passive");
+    assert_eq!(fake.read("intel_pstate/max_perf_pct").unwrap(), "80");
+    assert_eq!(
+        fake.read("cpu0/cpufreq/scaling_driver").unwrap(),
+        "intel_cpufreq"
+    );
+
+    let out = mdcr(&fake, &["performance"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8(out.stderr).unwrap().contains("80%"));
+    assert_eq!(
+        fake.read("cpu0/cpufreq/energy_performance_preference")
+            .unwrap(),
+        "performance"
+    );
+
+    let out = mdcr(&fake, &["intel-pstate", "--max-perf-pct", "120"]);
+    assert_eq!(out.status.code(), Some(22));
+
+    // a status mdcr does not know leaves the rest of show intact
+    fake.write("intel_pstate/status", "unknown").unwrap();
+    let out = mdcr(&fake, &["show"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8(out.stdout)
+        .unwrap()
+        .contains("Current Settings"));
+    assert!(String::from_utf8(out.stderr)
+        .unwrap()
+        .contains("Warning: Failed to discover intel_pstate settings"));
+}
+
+#[test]
+fn amd_pstate() {
+    let fake = FakeSysfs::builder()
+        .amd_pstate("passive")
+        .core_prefcore_ranking(3, 236)
+        .core_prefcore_ranking(1, 200)
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["show"]);
+    let table = String::from_utf8(out.stdout).unwrap();
+    assert!(table.contains("Prefcore Ranking"));
+    assert!(table.contains("* Preferred Cores        3 1 0 2"));
+
+    let out = mdcr(&fake, &["amd-pstate", "--status", "active"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8(out.stdout)
+        .unwrap()
+        .contains("amd-pstate             active"));
+    assert_eq!(
+        fake.read("cpu0/cpufreq/scaling_driver").unwrap(),
+        "amd-pstate-epp"
+    );
+
+    let out = mdcr(&fake, &["amd-pstate", "--status", "off"]);
+    assert!(!out.status.success());
+
+    let out = mdcr(&fake, &["amd-pstate", "--status", "disable"]);
+    assert!(out.status.success());
+    assert!(String::from_utf8(out.stdout)
+        .unwrap()
+        .contains("amd-pstate             disable"));
+
+    fake.write("amd_pstate/status", "unknown").unwrap();
+    let out = mdcr(&fake, &["show"]);
+    assert!(out.status.success());
+    let table = String::from_utf8(out.stdout).unwrap();
+    assert!(!table.contains("* amd-pstate"));
+    assert!(table.contains("* Preferred Cores"));
+    assert!(String::from_utf8(out.stderr)
+        .unwrap()
+        .contains("Warning: Failed to discover amd-pstate settings"));
+}
+
+#[test]
+fn epp() {
+    let fake = FakeSysfs::builder()
+        .epp("balance_performance")
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["set", "-c", "1", "-e", "balance_power"]);
+    assert!(out.status.success());
+    assert_eq!(
+        fake.read("cpu1/cpufreq/energy_performance_preference")
+            .unwrap(),
+        "balance_power"
+    );
+    let out = mdcr(&fake, &["set", "--epp", "300"]);
+    assert_eq!(out.status.code(), Some(22));
+    let out = mdcr(&fake, &["set", "--epp", "128"]);
+    assert!(out.status.success());
+
+    // refused up front instead of halfway through the cores
+    let amd = FakeSysfs::builder()
+        .amd_pstate("active")
+        .epp("balance_performance")
+        .build()
+        .unwrap();
+    let out = mdcr(&amd, &["set", "--epp", "128"]);
+    assert_eq!(out.status.code(), Some(22));
+    assert_eq!(
+        amd.read("cpu0/cpufreq/energy_performance_preference")
+            .unwrap(),
+        "balance_performance"
+    );
+
+    let out = mdcr(&fake, &["powersave"]);
+    assert!(out.status.success());
+    assert_eq!(
+        fake.read("cpu0/cpufreq/energy_performance_preference")
+            .unwrap(),
+        "power"
+    );
+    let out = mdcr(&fake, &["show"]);
+    assert!(String::from_utf8(out.stdout)
+        .unwrap()
+        .contains("Energy Perf Pref        power"));
+}
+
+#[test]
+fn epb() {
+    let fake = FakeSysfs::builder().epb(0).build().unwrap();
+    let out = mdcr(&fake, &["set", "-c", "2-3", "--epb", "balance-power"]);
+    assert!(out.status.success());
+    assert_eq!(fake.read("cpu3/power/energy_perf_bias").unwrap(), "8");
+    assert_eq!(fake.read("cpu1/power/energy_perf_bias").unwrap(), "0");
+
+    let out = mdcr(&fake, &["show"]);
+    assert!(String::from_utf8(out.stdout)
+        .unwrap()
+        .contains("Energy Perf Bias"));
+
+    let out = mdcr(&fake, &["set", "--epb", "16"]);
+    assert!(!out.status.success());
+}
+
+#[test]
+fn tune() {
+    let fake = FakeSysfs::builder()
+        .cores(2)
+        .governors(&["ondemand", "performance"])
+        .build()
+        .unwrap();
+    let out = mdcr(
+        &fake,
+        &["tune", "ondemand", "up_threshold=90", "io_is_busy=1"],
+    );
+    assert!(out.status.success());
+    assert_eq!(fake.read("cpufreq/ondemand/up_threshold").unwrap(), "90");
+    assert_eq!(fake.read("cpufreq/ondemand/io_is_busy").unwrap(), "1");
+
+    // nothing is written if any value is invalid
+    let out = mdcr(
+        &fake,
+        &["tune", "ondemand", "up_threshold=70", "io_is_busy=2"],
+    );
+    assert_eq!(out.status.code(), Some(22));
+    assert_eq!(fake.read("cpufreq/ondemand/up_threshold").unwrap(), "90");
+
+    let out = mdcr(&fake, &["tune", "ondemand"]);
+    assert!(String::from_utf8(out.stdout)
+        .unwrap()
+        .contains("up_threshold"));
+
+    let out = mdcr(&fake, &["tune", "conservative", "freq_step=10"]);
+    assert_eq!(out.status.code(), Some(95));
+}
+
+#[test]
+fn pin() {
+    let fake = FakeSysfs::builder()
+        .cores(4)
+        .intel_pstate("passive")
+        .build()
+        .unwrap();
+    let out = mdcr(&fake, &["pin", "2GHz", "-c", "2-3"]);
+    assert!(out.status.success());
+    assert_eq!(
+        fake.read("cpu2/cpufreq/scaling_governor").unwrap(),
+        "userspace"
+    );
+    assert_eq!(
+        fake.read("cpu3/cpufreq/scaling_setspeed").unwrap(),
+        "2000000"
+    );
+    assert_eq!(
+        fake.read("cpu1/cpufreq/scaling_setspeed").unwrap(),
+        "<unsupported>"
+    );
+    assert!(String::from_utf8(out.stdout).unwrap().contains("Pinned"));
+
+    let out = mdcr(&fake, &["pin", "5GHz"]);
+    assert_eq!(out.status.code(), Some(22));
+
+    // intel_pstate in active mode has no userspace governor
+    let fake = FakeSysfs::builder().cores(2).build().unwrap();
+    let out = mdcr(&fake, &["pin", "2GHz"]);
+    assert_eq!(out.status.code(), Some(95));
+    assert!(String::from_utf8(out.stderr)
+        .unwrap()
+        .contains("intel-pstate --status passive"));
+}
+
+#[test]
+fn stats() {
+    let fake = FakeSysfs::builder()
+        .cores(2)
+        .intel_pstate("passive")
+        .stats()
+        .build()
+        .unwrap();
+    fake.write(
+        "cpufreq/policy1/stats/time_in_state",
+        "3000000 2500\n800000 7500",
+    )
+    .unwrap();
+    let out = mdcr(&fake, &["stats", "-c", "1", "--reset"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("Policy 1 (cores 1)"));
+    assert!(!stdout.contains("Policy 0"));
+    assert!(stdout.contains(" 75.0%  0h 01m 15s"));
+    assert_eq!(
+        fake.read("cpufreq/policy1/stats/time_in_state").unwrap(),
+        "3000000 0\n800000 0"
+    );
+
+    let fake = FakeSysfs::builder().build().unwrap();
+    let out = mdcr(&fake, &["stats"]);
+    assert_eq!(out.status.code(), Some(95));
+}
+
+#[test]
+fn idle() {
+    let fake = FakeSysfs::builder().cores(4).cpuidle().build().unwrap();
+    let out = mdcr(
+        &fake,
+        &["idle", "disable", "--state", "C6", "-s", "C1E", "-c", "0-2"],
+    );
+    assert!(out.status.success());
+    assert_eq!(fake.read("cpu2/cpuidle/state3/disable").unwrap(), "1");
+    assert_eq!(fake.read("cpu2/cpuidle/state2/disable").unwrap(), "1");
+    assert_eq!(fake.read("cpu3/cpuidle/state3/disable").unwrap(), "0");
+
+    let out = mdcr(&fake, &["idle", "enable", "--state", "c6", "-c", "2"]);
+    assert!(out.status.success());
+    assert_eq!(fake.read("cpu2/cpuidle/state3/disable").unwrap(), "0");
+
+    let out = mdcr(&fake, &["idle", "show"]);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("intel_idle, governor menu"));
+    let c6 = stdout.lines().find(|l| l.starts_with("C6")).unwrap();
+    assert!(c6.contains("133us") && c6.contains("0-1"));
+
+    let out = mdcr(&fake, &["idle", "disable", "--state", "C10"]);
+    assert_eq!(out.status.code(), Some(22));
+
+    let fake = FakeSysfs::builder().build().unwrap();
+    let out = mdcr(&fake, &["idle", "show"]);
+    assert_eq!(out.status.code(), Some(95));
+}
+
+#[test]
+fn latency() {
+    let fake = FakeSysfs::builder().build().unwrap();
+    let device = fake.path("../../../dev/cpu_dma_latency");
+    fake.write(&device, "").unwrap();
+    let device = device.to_str().unwrap();
+
+    // the command sees the hold while it runs and its exit code is passed on
+    let out = mdcr(
+        &fake,
+        &[
+            "latency",
+            "--max-us",
+            "20",
+            "--device",
+            device,
+            "--",
+            "sh",
+            "-c",
+            &format!("head -c 4 {}; exit 3", device),
+        ],
+    );
+    assert_eq!(out.status.code(), Some(3));
+    assert_eq!(out.stdout, 20i32.to_ne_bytes());
+
+    let out = mdcr(
+        &fake,
+        &[
+            "latency",
+            "--max-us",
+            "20",
+            "--device",
+            device,
+            "--",
+            "/nonexistent",
+        ],
+    );
+    assert_eq!(out.status.code(), Some(127));
+}
+
+#[test]
+fn thermal() {
+    let fake = FakeSysfs::builder()
+        .cores(2)
+        .thermal_zone("x86_pkg_temp", 97_000)
+        .cooling_device("Processor", 2, 10)
+        .cooling_device("Fan", 0, 1)
+        .build()
+        .unwrap();
+    fake.write(fake.thermal_root().join("cooling_device1/max_state"), "-1")
+        .unwrap();
+    let thermal_root = fake.thermal_root();
+    let thermal_root = thermal_root.to_str().unwrap();
+
+    // the host's readings do not belong to a fake tree
+    let out = mdcr(&fake, &["show", "--thermal"]);
+    assert_eq!(out.status.code(), Some(22));
+
+    let out = mdcr(
+        &fake,
+        &["--thermal-root", thermal_root, "show", "--thermal"],
+    );
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("x86_pkg_temp           97.0°C (trips: passive 95.0°C"));
+    assert!(stdout.contains("Processor              2/10 cooling"));
+    assert!(stdout.contains(&format!("{:<22} 0/-\n", "Fan")));
+
+    let out = mdcr(
+        &fake,
+        &[
+            "--thermal-root",
+            thermal_root,
+            "show",
+            "--json",
+            "--thermal",
+        ],
+    );
+    let json = String::from_utf8(out.stdout).unwrap();
+    assert!(json.trim_start().starts_with('{'));
+    assert_eq!(json.matches("\"num\"").count(), 5);
+    assert!(json.contains("\"type\": \"x86_pkg_temp\""));
+    assert!(json.contains("\"cur_state\": 2"));
+    assert!(json.contains("\"max_state\": null"));
+
+    // without --thermal the json stays a list of cores
+    let out = mdcr(&fake, &["show", "--json"]);
+    let json = String::from_utf8(out.stdout).unwrap();
+    assert!(json.trim_start().starts_with('['));
+    assert!(!json.contains("x86_pkg_temp"));
+}
@@ -0,0 +1,30 @@
+//! mdcr with the kernel emulation of mediocore::fake installed, run by tests/mdcr.rs.
+//! Only built with the fake-sysfs feature.
+#[macro_use]
+extern crate log;
+extern crate mediocore;
+extern crate serde;
+extern crate serde_json;
+extern crate structopt;
+
+use std::env;
+
+use mediocore::fake;
+
+#[path = "../../src/bin/mdcr/cli.rs"]
+mod cli;
+
+fn main() {
+    // the tree was built by the test, so attach the one handed to mdcr
+    let args = env::args().collect::<Vec<_>>();
+    if let Some(root) = args.windows(2).find(|a| a[0] == "--sysfs-root") {
+        fake::emulation()
+            .attach(&root[1])
+            .expect("Not a fake sysfs tree");
+    }
+    if mediocore::set_backend(fake::emulation()).is_err() {
+        panic!("Another backend is installed");
+    }
+
+    cli::main()
+}